@@ -1,115 +1,378 @@
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
 use anyhow::Result;
-use std::path::PathBuf;
-use dialoguer::Select;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::check::{check, repair, Issue};
 use crate::i18n::{Language, I18n};
+use crate::config::{ProjectConfig, UserConfig};
 use crate::file_ops::{ensure_directories, navigate_directory};
-use crate::generator::{generate_controller, generate_service};
+use crate::format::Formatter;
+use crate::mod_file::{sort_module_files, ModOrder};
+use crate::layer::{Layer, LayerSelection};
+use crate::openapi::import_openapi;
+use crate::plan::{unified_diff, GenerationPlan, GenerationRequest, Generator};
+use crate::list::{list, Endpoint};
+use crate::refactor::{move_module, rename, Relocation};
+use crate::remove::remove;
+use crate::report::{CheckReport, DependencyReport, ListReport, Report, Warning};
+use crate::prompt::{DialoguerPrompter, Prompter};
+use crate::tui;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
+#[command(subcommand_negates_reqs = true, args_conflicts_with_subcommands = true)]
 pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// Target directory for code generation
-    pub target: PathBuf,
+    #[arg(required = true)]
+    pub target: Option<PathBuf>,
 
     /// Framework to use (default: axum)
-    #[arg(short, long, default_value = "axum")]
+    #[arg(short, long, default_value = "axum", global = true)]
     pub framework: String,
 
-    /// Language (en or zh, default: en)
-    #[arg(short, long, default_value = "en")]
-    pub language: String,
+    /// Language (en, zh, zh-TW, ja or ko); defaults to the remembered choice,
+    /// then to the locale from LC_ALL/LC_MESSAGES/LANG, then to en
+    #[arg(short, long, global = true)]
+    pub language: Option<String>,
 
     /// Controller directory name (default: controller)
-    #[arg(long, default_value = "controller")]
+    #[arg(long, default_value = "controller", global = true)]
     pub controller_dir: String,
 
     /// Service directory name (default: service)
-    #[arg(long, default_value = "service")]
+    #[arg(long, default_value = "service", global = true)]
     pub service_dir: String,
+
+    /// Emit request/response DTOs into this directory instead of the controller file
+    #[arg(long, global = true)]
+    pub dto_dir: Option<String>,
+
+    /// Also generate an integration test for each new handler in this directory below the
+    /// crate root, e.g. tests/api
+    #[arg(long, global = true)]
+    pub tests_dir: Option<String>,
+
+    /// Language of comments in generated code (default: the UI language)
+    #[arg(long, global = true)]
+    pub code_language: Option<String>,
+
+    /// Fill in the form in a full-screen terminal UI with a live preview
+    #[arg(long)]
+    pub tui: bool,
+
+    /// Formatter for generated files (default: the project's `format` setting)
+    #[arg(long, value_enum, global = true)]
+    pub format: Option<Formatter>,
+
+    /// Answer yes instead of asking: add missing dependencies, and delete without confirmation in remove
+    #[arg(short, long, global = true)]
+    pub yes: bool,
+
+    /// Never add missing dependencies; report them as warnings instead
+    #[arg(long, global = true, conflicts_with = "yes")]
+    pub no_deps: bool,
+
+    /// How to report the generated files; with json, other messages go to stderr
+    #[arg(long, value_enum, default_value = "text", global = true)]
+    pub output: OutputFormat,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Generate controllers and services from an OpenAPI 3 document
+    ImportOpenapi {
+        /// OpenAPI document (yaml or json)
+        spec: PathBuf,
+
+        /// Target directory for code generation (default: current directory)
+        #[arg(default_value = ".")]
+        target: PathBuf,
+    },
+    /// Sort the `mod` declarations in every mod.rs/lib.rs/main.rs below a directory
+    FmtMods {
+        /// Directory to scan (default: current directory)
+        #[arg(default_value = ".")]
+        target: PathBuf,
+    },
+    /// Delete a module of the outermost layer and the inner-layer modules only it calls,
+    /// with their `mod` declarations and route registrations
+    Remove {
+        /// Module path below the outermost layer, e.g. admin/user or admin::user
+        module: String,
+
+        /// Project directory (default: current directory)
+        #[arg(default_value = ".")]
+        target: PathBuf,
+    },
+    /// Rename a module of the outermost layer and the modules it calls, with their types and imports
+    Rename {
+        /// Module path below the outermost layer, e.g. admin/user or admin::user
+        module: String,
+
+        /// New module name, e.g. account
+        new_name: String,
+
+        /// Project directory (default: current directory)
+        #[arg(default_value = ".")]
+        target: PathBuf,
+
+        /// Print the changes as diffs without writing them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Move a module of the outermost layer and the modules it calls to another subdirectory
+    Move {
+        /// Module path below the outermost layer, e.g. admin/user or admin::user
+        module: String,
+
+        /// Subdirectory to move it to in every layer, e.g. api
+        new_dir: String,
+
+        /// Project directory (default: current directory)
+        #[arg(default_value = ".")]
+        target: PathBuf,
+
+        /// Print the changes as diffs without writing them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Report undeclared modules, dangling declarations and broken calls between layers;
+    /// exits with an error when anything is found
+    Check {
+        /// Project directory (default: current directory)
+        #[arg(default_value = ".")]
+        target: PathBuf,
+
+        /// Declare undeclared modules and remove dangling declarations, printing a diff of each change
+        #[arg(long)]
+        fix: bool,
+    },
+    /// List the handlers of the outermost layer with their extractors, services and routes
+    List {
+        /// Project directory (default: current directory)
+        #[arg(default_value = ".")]
+        target: PathBuf,
+    },
 }
 
 impl Cli {
     pub fn run(&self) -> Result<()> {
-        // Validate language
-        let lang = match self.language.as_str() {
-            "en" | "zh" => Language::from_str(&self.language),
-            _ => {
-                println!("{}", I18n::invalid_language(&self.language));
-                let selection = Select::new()
-                    .with_prompt(I18n::select_language_prompt())
-                    .items(&["en (English)", "zh (中文)"])
-                    .default(0)
-                    .interact()?;
-                
-                match selection {
-                    0 => Language::En,
-                    1 => Language::Zh,
-                    _ => Language::En,
+        self.run_with(&DialoguerPrompter)
+    }
+
+    /// Run the command, asking every question through `prompter`.
+    pub fn run_with(&self, prompter: &dyn Prompter) -> Result<()> {
+        let lang = self.resolve_language(prompter)?;
+        let i18n = I18n::new(lang);
+        let code_language = match &self.code_language {
+            Some(code) => match Language::find(code) {
+                Some(code_language) => code_language,
+                None => anyhow::bail!("{}", I18n::invalid_language(code)),
+            },
+            None => lang,
+        };
+
+        match &self.command {
+            Some(Command::ImportOpenapi { spec, target }) => {
+                self.ensure_target(target, &i18n)?;
+                let config = ProjectConfig::load(target)?;
+                let layers = config.layers(&self.controller_dir, &self.service_dir);
+                let generator = self.generator(target, layers, code_language, prompter, &i18n)?;
+                let plan = import_openapi(spec, &generator)?;
+                plan.apply()?;
+                let mut report = Report::new(&plan);
+                self.print_changes(&plan, &i18n);
+                self.say(format!("\n{}", i18n.openapi_imported(plan.routes.len())));
+                self.add_dependencies(&plan, &mut report, prompter, &i18n)?;
+                self.print_report(&report)
+            }
+            Some(Command::FmtMods { target }) => {
+                self.ensure_target(target, &i18n)?;
+                let plan = sort_module_files(target)?;
+                plan.apply()?;
+                self.print_changes(&plan, &i18n);
+                if plan.changes.is_empty() {
+                    self.say(i18n.mod_files_sorted());
+                }
+                self.print_report(&Report::new(&plan))
+            }
+            Some(Command::Remove { module, target }) => {
+                self.ensure_target(target, &i18n)?;
+                let removal = remove(&self.project_generator(target)?, module)?;
+                self.say(i18n.files_to_remove());
+                for path in &removal.plan.removed {
+                    self.say(format!("  {}", path.display()));
+                }
+                // Without a terminal there is nobody to confirm, so deleting takes an explicit --yes
+                if !self.yes && !prompter.is_interactive() {
+                    anyhow::bail!("{}", i18n.remove_needs_yes());
                 }
+                if !self.yes && !prompter.confirm(&i18n.confirm_remove(), false)? {
+                    self.say(i18n.task_ended());
+                    return Ok(());
+                }
+
+                removal.plan.apply()?;
+                self.print_changes(&removal.plan, &i18n);
+                let mut report = Report::new(&removal.plan);
+                if !removal.references.is_empty() {
+                    self.say(format!("\n{}", i18n.remaining_references()));
+                }
+                for reference in removal.references {
+                    self.say(format!("  {}:{}: {}", reference.path.display(), reference.line, reference.text));
+                    report.warnings.push(Warning::RemainingReference(reference));
+                }
+                self.print_report(&report)
+            }
+            Some(Command::Rename { module, new_name, target, dry_run }) => {
+                self.ensure_target(target, &i18n)?;
+                let relocation = rename(&self.project_generator(target)?, module, new_name)?;
+                self.relocate(relocation, *dry_run, &i18n)
+            }
+            Some(Command::Move { module, new_dir, target, dry_run }) => {
+                self.ensure_target(target, &i18n)?;
+                let relocation = move_module(&self.project_generator(target)?, module, new_dir)?;
+                self.relocate(relocation, *dry_run, &i18n)
+            }
+            Some(Command::Check { target, fix }) => {
+                self.ensure_target(target, &i18n)?;
+                let generator = self.project_generator(target)?;
+                let mut issues = check(&generator)?;
+                let mut modified = Vec::new();
+                if *fix {
+                    let plan = repair(&generator, &issues)?;
+                    plan.apply()?;
+                    for change in &plan.changes {
+                        self.say(change.diff());
+                    }
+                    modified = plan.changes.into_iter().map(|change| change.path).collect();
+                    // Report only what is left to fix by hand
+                    issues = check(&generator)?;
+                }
+                for issue in &issues {
+                    self.say(describe_issue(issue, &i18n));
+                }
+                self.print_report(&CheckReport { issues: issues.clone(), modified })?;
+                if !issues.is_empty() {
+                    anyhow::bail!("{}", i18n.check_failed(issues.len()));
+                }
+                self.say(i18n.check_passed());
+                Ok(())
+            }
+            Some(Command::List { target }) => {
+                self.ensure_target(target, &i18n)?;
+                let endpoints = list(&self.project_generator(target)?)?;
+                if self.output == OutputFormat::Json {
+                    return self.print_report(&ListReport { endpoints });
+                }
+                if endpoints.is_empty() {
+                    self.say(i18n.no_endpoints(&target.display().to_string()));
+                } else {
+                    self.say(endpoint_table(&endpoints, &i18n));
+                }
+                Ok(())
             }
+            None => {
+                let target = self.target.as_deref().expect("target is required without a subcommand");
+                self.generate(target, code_language, prompter, &i18n)
+            }
+        }
+    }
+
+    fn resolve_language(&self, prompter: &dyn Prompter) -> Result<Language> {
+        let config_path = UserConfig::path();
+        let user_config = match &config_path {
+            Some(path) => UserConfig::load(path)?,
+            None => UserConfig::default(),
         };
 
-        let i18n = I18n::new(lang);
+        // An explicit flag wins over the remembered choice, which wins over the environment
+        let Some(requested) = self.language.as_deref().or(user_config.language.as_deref()) else {
+            return Ok(Language::from_env().unwrap_or(Language::EN));
+        };
+        if let Some(lang) = Language::find(requested) {
+            return Ok(lang);
+        }
+
+        self.say(I18n::invalid_language(requested));
+        let languages: Vec<Language> = Language::all().collect();
+        let options: Vec<String> = languages
+            .iter()
+            .map(|lang| format!("{} ({})", lang.code(), lang.name()))
+            .collect();
+        let selection = prompter.select(&I18n::select_language_prompt(), &options, 0)?;
 
+        // Remember the choice so the prompt is not repeated
+        let lang = languages[selection];
+        if let Some(path) = &config_path {
+            UserConfig::save_language(path, lang.code())?;
+        }
+        Ok(lang)
+    }
+
+    fn ensure_target(&self, target: &Path, i18n: &I18n) -> Result<()> {
         // Ensure target directory exists
-        if !self.target.exists() {
-            anyhow::bail!("{}: {}", i18n.target_not_found(), self.target.display());
+        if !target.exists() {
+            anyhow::bail!("{}: {}", i18n.target_not_found(), target.display());
         }
+        Ok(())
+    }
 
-        // Check and create controller and service directories
-        let controller_path = self.target.join(&self.controller_dir);
-        let service_path = self.target.join(&self.service_dir);
+    fn generate(&self, target: &Path, code_language: Language, prompter: &dyn Prompter, i18n: &I18n) -> Result<()> {
+        self.ensure_target(target, i18n)?;
 
-        let (controller_exists, service_exists) = (
-            controller_path.exists(),
-            service_path.exists(),
-        );
+        let config = ProjectConfig::load(target)?;
+        let layers = config.layers(&self.controller_dir, &self.service_dir);
+        let generator = self.generator(target, layers, code_language, prompter, i18n)?;
+        let layer_paths: Vec<PathBuf> = generator.resolve_locations()?.into_iter().map(|location| location.path).collect();
 
-        if !controller_exists || !service_exists {
-            let mut missing = Vec::new();
-            if !controller_exists {
-                missing.push(&self.controller_dir);
-            }
-            if !service_exists {
-                missing.push(&self.service_dir);
-            }
+        // Check and create layer directories
+        let missing: Vec<&String> = generator
+            .layers
+            .iter()
+            .zip(&layer_paths)
+            .filter(|(_, path)| !path.exists())
+            .map(|(layer, _)| &layer.dir)
+            .collect();
 
-            println!("{}", i18n.directories_not_found(&missing));
+        if !missing.is_empty() {
+            self.say(i18n.directories_not_found(&missing));
             
             let options = vec![i18n.create_option(), i18n.cancel_option()];
-            let selection = Select::new()
-                .with_prompt(i18n.select_action_prompt())
-                .items(&options)
-                .default(0)
-                .interact()?;
+            let selection = prompter.select(&i18n.select_action_prompt(), &options, 0)?;
 
             match selection {
                 0 => {
                     // Create missing directories
-                    ensure_directories(&controller_path, &service_path)?;
-                    println!("{}", i18n.directories_created());
+                    ensure_directories(&layer_paths)?;
+                    self.say(i18n.directories_created());
                 }
                 1 => {
-                    println!("{}", i18n.task_cancelled(&missing));
+                    self.say(i18n.task_cancelled(&missing));
                     
                     let final_options = vec![
                         i18n.create_directories_option(&missing),
                         i18n.cancel_and_exit_option(),
                     ];
-                    let final_selection = Select::new()
-                        .with_prompt(i18n.select_action_prompt())
-                        .items(&final_options)
-                        .default(0)
-                        .interact()?;
+                    let final_selection = prompter.select(&i18n.select_action_prompt(), &final_options, 0)?;
 
                     match final_selection {
                         0 => {
-                            ensure_directories(&controller_path, &service_path)?;
-                            println!("{}", i18n.directories_created());
+                            ensure_directories(&layer_paths)?;
+                            self.say(i18n.directories_created());
                         }
                         1 => {
-                            println!("{}", i18n.task_ended());
+                            self.say(i18n.task_ended());
                             return Ok(());
                         }
                         _ => return Ok(()),
@@ -119,76 +382,219 @@ impl Cli {
             }
         }
 
-        // Navigate service directory and get file info first
-        println!("\n{}", i18n.navigate_service());
-        let service_subdir = navigate_directory(&service_path, &self.service_dir, &i18n)?;
-        let service_file_name = self.get_file_name(&i18n, i18n.service_file_prompt())?;
-        let service_method_name = self.get_method_name(&i18n, &service_file_name)?;
+        let (selections, route) = if self.tui {
+            match tui::run(&generator, i18n)? {
+                Some(selection) => (selection.selections, selection.route),
+                None => {
+                    self.say(i18n.task_ended());
+                    return Ok(());
+                }
+            }
+        } else {
+            (self.prompt_selections(&generator.layers, &layer_paths, config.mod_order, prompter, i18n)?, None)
+        };
+
+        let plan = generator.plan(&GenerationRequest { selections, route })?;
+        plan.apply()?;
+        let mut report = Report::new(&plan);
+        self.print_changes(&plan, i18n);
+        self.add_dependencies(&plan, &mut report, prompter, i18n)?;
+
+        self.say(format!("\n{}", i18n.generation_complete()));
+        self.print_report(&report)
+    }
+
+    /// Navigate the innermost layer first, so outer layers know what they call into.
+    fn prompt_selections(
+        &self,
+        layers: &[Layer],
+        layer_paths: &[PathBuf],
+        mod_order: ModOrder,
+        prompter: &dyn Prompter,
+        i18n: &I18n,
+    ) -> Result<Vec<LayerSelection>> {
+        let mut selections = Vec::with_capacity(layers.len());
+        for (i, (layer, layer_path)) in layers.iter().zip(layer_paths).enumerate().rev() {
+            self.say(format!("\n{}", i18n.navigate_layer(&layer.name)));
+            // Offer the subpath chosen for the inner layer as the default
+            let mirror = selections
+                .last()
+                .map(|inner: &LayerSelection| (inner.subdir.as_path(), layers[i + 1].name.as_str()));
+            let subdir = navigate_directory(layer_path, &layer.dir, mirror, layer.mod_policy(mod_order), prompter, i18n)?;
+            let file_name = prompter.input(&i18n.layer_file_prompt(&layer.name), false, None)?;
+            let method_name = self.get_method_name(prompter, i18n, &file_name)?;
+            let file_name = self.apply_suffix(prompter, i18n, layer, file_name)?;
+            selections.push(LayerSelection { subdir, file_name, method_name });
+        }
+        selections.reverse();
+        Ok(selections)
+    }
+
+    /// Generator for the chosen layers, honouring `--dto-dir` and `--tests-dir` over the project config.
+    /// In a cargo workspace, asks which member crate holds each layer unless configured.
+    fn generator(
+        &self,
+        target: &Path,
+        layers: Vec<Layer>,
+        code_language: Language,
+        prompter: &dyn Prompter,
+        i18n: &I18n,
+    ) -> Result<Generator> {
+        let mut generator = Generator::new(target)?.layers(layers).comment_language(code_language);
+        if let Some(dto_dir) = &self.dto_dir {
+            generator = generator.dto_dir(dto_dir);
+        }
+        if let Some(tests_dir) = &self.tests_dir {
+            generator = generator.tests_dir(tests_dir);
+        }
+        if let Some(formatter) = self.format {
+            generator = generator.formatter(formatter);
+        }
+        let locations = generator.resolve_locations_with(|layer, names, default| {
+            prompter.select(&i18n.select_layer_crate(&layer.name), names, default)
+        })?;
+        Ok(generator.locations(locations))
+    }
+
+    /// Generator for a command that works on the existing project without prompting.
+    fn project_generator(&self, target: &Path) -> Result<Generator> {
+        let config = ProjectConfig::load(target)?;
+        let generator = Generator::new(target)?.layers(config.layers(&self.controller_dir, &self.service_dir));
+        Ok(match &self.dto_dir {
+            Some(dto_dir) => generator.dto_dir(dto_dir),
+            None => generator,
+        })
+    }
+
+    /// Apply a rename or move, or with `dry_run` only show it as diffs.
+    fn relocate(&self, relocation: Relocation, dry_run: bool, i18n: &I18n) -> Result<()> {
+        let Relocation { plan, moves, references } = relocation;
+        if dry_run {
+            for change in &plan.changes {
+                match moves.iter().find(|moved| moved.to == change.path) {
+                    Some(moved) => {
+                        let before = fs::read_to_string(&moved.from)?;
+                        self.say(unified_diff(&moved.from.display().to_string(), &before, &change.path.display().to_string(), &change.content));
+                    }
+                    None => self.say(change.diff()),
+                }
+            }
+            self.say(i18n.dry_run_notice());
+        } else {
+            plan.apply()?;
+            for moved in &moves {
+                self.say(i18n.file_moved(&moved.from.display().to_string(), &moved.to.display().to_string()));
+            }
+            let moved = |path: &PathBuf| moves.iter().any(|m| &m.to == path || &m.from == path);
+            let others = GenerationPlan {
+                changes: plan.changes.iter().filter(|change| !moved(&change.path)).cloned().collect(),
+                ..GenerationPlan::default()
+            };
+            self.print_changes(&others, i18n);
+        }
+
+        let mut report = Report::new(&plan);
+        if !references.is_empty() {
+            self.say(format!("\n{}", i18n.remaining_references()));
+        }
+        for reference in references {
+            self.say(format!("  {}:{}: {}", reference.path.display(), reference.line, reference.text));
+            report.warnings.push(Warning::RemainingReference(reference));
+        }
+        self.print_report(&report)
+    }
+
+    /// Print a progress message; kept off stdout when it carries the JSON report.
+    fn say(&self, message: impl std::fmt::Display) {
+        match self.output {
+            OutputFormat::Text => println!("{}", message),
+            OutputFormat::Json => eprintln!("{}", message),
+        }
+    }
+
+    /// Print the files a plan created, updated or skipped.
+    fn print_changes(&self, plan: &GenerationPlan, i18n: &I18n) {
+        for change in &plan.changes {
+            let path = change.path.display().to_string();
+            if change.is_new() {
+                self.say(i18n.file_created(&path));
+            } else {
+                self.say(i18n.mod_updated(&path));
+            }
+        }
+        for path in &plan.removed {
+            self.say(i18n.file_removed(&path.display().to_string()));
+        }
+        for path in &plan.skipped {
+            self.say(i18n.file_exists_skipped(&path.display().to_string()));
+        }
+        for file in &plan.unformatted {
+            self.say(i18n.file_unformatted(&file.path.display().to_string(), &file.error));
+        }
+    }
+
+    fn print_report(&self, report: &impl Serialize) -> Result<()> {
+        if self.output == OutputFormat::Json {
+            println!("{}", serde_json::to_string_pretty(report)?);
+        }
+        Ok(())
+    }
+
+    /// Offer to add the dependencies the generated files need to each Cargo.toml. With `--yes`
+    /// they are added without asking; with `--no-deps`, JSON output or nobody at the terminal
+    /// they are only reported as warnings.
+    fn add_dependencies(
+        &self,
+        plan: &GenerationPlan,
+        report: &mut Report,
+        prompter: &dyn Prompter,
+        i18n: &I18n,
+    ) -> Result<()> {
+        let ask = !self.no_deps && self.output == OutputFormat::Text && prompter.is_interactive();
+        for change in &plan.dependencies {
+            let path = change.manifest.display().to_string();
+            self.say(format!("\n{}", i18n.missing_dependencies(&path, &change.names())));
+            let add = if self.yes {
+                true
+            } else if ask {
+                let options = vec![i18n.add_dependencies_option(), i18n.skip_option()];
+                prompter.select(&i18n.select_action_prompt(), &options, 0)? == 0
+            } else {
+                false
+            };
+            if !add {
+                report.warnings.push(Warning::MissingDependencies(DependencyReport::from(change)));
+                continue;
+            }
+
+            change.apply()?;
+            report.dependencies.push(DependencyReport::from(change));
+            self.say(i18n.mod_updated(&path));
+        }
+
+        Ok(())
+    }
+
+    /// Ask whether to append the layer's naming suffix, if it has one.
+    fn apply_suffix(&self, prompter: &dyn Prompter, i18n: &I18n, layer: &Layer, file_name: String) -> Result<String> {
+        if layer.suffix.is_empty() || file_name.ends_with(&layer.suffix) {
+            return Ok(file_name);
+        }
 
-        // Ask about _service suffix
         let options = vec![
-            i18n.add_service_suffix_option(),
+            i18n.add_suffix_option(&layer.suffix),
             i18n.skip_option(),
         ];
-        let selection = Select::new()
-            .with_prompt(i18n.service_suffix_prompt())
-            .items(&options)
-            .default(0)
-            .interact()?;
-
-        let final_service_file_name = match selection {
-            0 => format!("{}_service", service_file_name),
-            _ => service_file_name.clone(),
-        };
+        let selection = prompter.select(&i18n.suffix_prompt(&layer.name), &options, 0)?;
 
-        // Navigate controller directory and get file info
-        println!("\n{}", i18n.navigate_controller());
-        let controller_subdir = navigate_directory(&controller_path, &self.controller_dir, &i18n)?;
-        let controller_file_name = self.get_file_name(&i18n, i18n.controller_file_prompt())?;
-        let controller_method_name = self.get_method_name(&i18n, &controller_file_name)?;
-
-        // Generate controller with service file name
-        let controller_full_path = controller_path.join(&controller_subdir);
-        generate_controller(
-            &controller_full_path,
-            &controller_file_name,
-            &controller_method_name,
-            &final_service_file_name,
-            &i18n,
-        )?;
-
-        // Generate service
-        let service_full_path = service_path.join(&service_subdir);
-        generate_service(
-            &service_full_path,
-            &final_service_file_name,
-            &service_method_name,
-            &i18n,
-        )?;
-
-        println!("\n{}", i18n.generation_complete());
-        Ok(())
+        match selection {
+            0 => Ok(format!("{}{}", file_name, layer.suffix)),
+            _ => Ok(file_name),
+        }
     }
 
-    fn get_file_name(&self, _i18n: &I18n, prompt: String) -> Result<String> {
-        use dialoguer::Input;
-        
-        let name: String = Input::new()
-            .with_prompt(prompt)
-            .interact_text()?;
-        
-        Ok(name.trim().to_string())
-    }
-
-    fn get_method_name(&self, i18n: &I18n, file_name: &str) -> Result<String> {
-        use dialoguer::Input;
-        
-        let method_name: String = Input::new()
-            .with_prompt(i18n.method_name_prompt())
-            .allow_empty(true)
-            .interact_text()?;
-        
-        let method_name = method_name.trim();
+    fn get_method_name(&self, prompter: &dyn Prompter, i18n: &I18n, file_name: &str) -> Result<String> {
+        let method_name = prompter.input(&i18n.method_name_prompt(), true, None)?;
         if method_name.is_empty() {
             Ok(file_name.to_string())
         } else {
@@ -196,3 +602,51 @@ impl Cli {
         }
     }
 }
+
+/// Handlers as a table, one row per handler with columns padded to the widest cell.
+fn endpoint_table(endpoints: &[Endpoint], i18n: &I18n) -> String {
+    let mut rows = vec![[i18n.list_module(), i18n.list_handler(), i18n.list_extractors(), i18n.list_service(), i18n.list_route()]];
+    for endpoint in endpoints {
+        let route = endpoint
+            .route
+            .as_ref()
+            .map(|route| format!("{} {}", route.method.to_uppercase(), route.path))
+            .unwrap_or_default();
+        rows.push([
+            endpoint.module.clone(),
+            endpoint.function.clone(),
+            endpoint.extractors.join(", "),
+            endpoint.services.join(", "),
+            route,
+        ]);
+    }
+    let widths: Vec<usize> = (0..5)
+        .map(|column| rows.iter().map(|row| console::measure_text_width(&row[column])).max().unwrap_or(0))
+        .collect();
+    rows.iter()
+        .map(|row| {
+            let cells: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{}{}", cell, " ".repeat(width - console::measure_text_width(cell))))
+                .collect();
+            cells.join("  ").trim_end().to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn describe_issue(issue: &Issue, i18n: &I18n) -> String {
+    match issue {
+        Issue::UndeclaredModule { path, mod_file, .. } => {
+            i18n.undeclared_module(&path.display().to_string(), &mod_file.display().to_string())
+        }
+        Issue::DanglingDeclaration { mod_file, module } => {
+            i18n.dangling_declaration(&mod_file.display().to_string(), module)
+        }
+        Issue::MissingItem { path, module, item } => i18n.missing_item(&path.display().to_string(), module, item),
+        Issue::Unreferenced { path, caller_layer } => {
+            i18n.unreferenced_module(&path.display().to_string(), caller_layer)
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml_edit::{value, DocumentMut, Item};
+use crate::format::Formatter;
+use crate::layer::{default_layers, Layer};
+use crate::manifest::{default_dependency, dependency_item};
+use crate::mod_file::ModOrder;
+
+/// Project config file looked up in the target directory.
+pub const CONFIG_FILE_NAME: &str = "laygen.toml";
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ProjectConfig {
+    /// Ordered layers, outermost first
+    #[serde(default)]
+    pub layers: Vec<Layer>,
+    /// Directory for request/response DTO modules; DTOs stay in the controller when unset
+    pub dto_dir: Option<String>,
+    /// Directory below the crate root for an integration test per new handler, e.g. `tests/api`;
+    /// no tests are generated when unset
+    pub tests_dir: Option<String>,
+    /// Versions/features used when adding missing dependencies, in Cargo.toml syntax
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, toml::Value>,
+    /// Formatter run on generated files
+    #[serde(default)]
+    pub format: Formatter,
+    /// Where declarations of new modules go in existing module files
+    #[serde(default)]
+    pub mod_order: ModOrder,
+}
+
+impl ProjectConfig {
+    /// Load `laygen.toml` from the target directory, or the defaults if there is none.
+    pub fn load(target: &Path) -> Result<Self> {
+        let path = target.join(CONFIG_FILE_NAME);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(&path)?;
+        toml::from_str(&content).with_context(|| format!("invalid config {}", path.display()))
+    }
+
+    /// Cargo.toml entry for a dependency the generated code needs.
+    pub fn dependency(&self, name: &str) -> Item {
+        match self.dependencies.get(name) {
+            Some(spec) => dependency_item(spec),
+            None => default_dependency(name),
+        }
+    }
+
+    /// Configured layers, falling back to the controller/service directories from the CLI.
+    pub fn layers(&self, controller_dir: &str, service_dir: &str) -> Vec<Layer> {
+        if self.layers.is_empty() {
+            default_layers(controller_dir, service_dir)
+        } else {
+            self.layers.clone()
+        }
+    }
+}
+
+/// Per-user settings, independent of the target project.
+#[derive(Debug, Default, Deserialize)]
+pub struct UserConfig {
+    /// UI language picked in the language prompt
+    pub language: Option<String>,
+}
+
+impl UserConfig {
+    /// `$LAYGEN_CONFIG`, else `laygen/config.toml` in the user's config directory.
+    pub fn path() -> Option<PathBuf> {
+        if let Some(path) = env::var_os("LAYGEN_CONFIG") {
+            return Some(PathBuf::from(path));
+        }
+        let dir = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(dir.join("laygen").join("config.toml"))
+    }
+
+    /// Load the user config at `path`, or the defaults if there is none.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(path)?;
+        toml::from_str(&content).with_context(|| format!("invalid config {}", path.display()))
+    }
+
+    /// Remember `language`, keeping everything else in the file as it is.
+    pub fn save_language(path: &Path, language: &str) -> Result<()> {
+        let content = if path.exists() { fs::read_to_string(path)? } else { String::new() };
+        let mut doc = content
+            .parse::<DocumentMut>()
+            .with_context(|| format!("invalid config {}", path.display()))?;
+        doc["language"] = value(language);
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, doc.to_string())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_layers() {
+        let config: ProjectConfig = toml::from_str(
+            r#"
+[[layers]]
+name = "controller"
+dir = "api"
+
+[[layers]]
+name = "repository"
+dir = "dao"
+template = "dao"
+suffix = "_dao"
+"#,
+        )
+        .unwrap();
+
+        let layers = config.layers("controller", "service");
+        assert_eq!(layers.len(), 2);
+        assert_eq!(layers[1].suffix, "_dao");
+        assert_eq!(
+            layers[1].module_import(Path::new("admin"), "user_dao"),
+            "crate::dao::admin::user_dao"
+        );
+        assert_eq!(ProjectConfig::default().layers("c", "s")[1].dir, "s");
+    }
+
+    #[test]
+    fn test_save_language_keeps_other_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("laygen/config.toml");
+        UserConfig::save_language(&path, "ja").unwrap();
+        assert_eq!(UserConfig::load(&path).unwrap().language.as_deref(), Some("ja"));
+
+        fs::write(&path, "# mine\nlanguage = \"ja\"\ntheme = \"dark\"\n").unwrap();
+        UserConfig::save_language(&path, "zh-TW").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "# mine\nlanguage = \"zh-TW\"\ntheme = \"dark\"\n");
+    }
+}
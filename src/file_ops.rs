@@ -1,46 +1,70 @@
 use anyhow::Result;
 use std::fs;
 use std::path::{Path, PathBuf};
-use dialoguer::Select;
+use walkdir::WalkDir;
 use crate::i18n::I18n;
+use crate::mod_file::ModPolicy;
+use crate::plan::GenerationPlan;
+use crate::prompt::Prompter;
 
-pub fn ensure_directories(controller_path: &Path, service_path: &Path) -> Result<()> {
-    if !controller_path.exists() {
-        fs::create_dir_all(controller_path)?;
-    }
-    if !service_path.exists() {
-        fs::create_dir_all(service_path)?;
+pub fn ensure_directories(paths: &[PathBuf]) -> Result<()> {
+    for path in paths {
+        if !path.exists() {
+            fs::create_dir_all(path)?;
+        }
     }
     Ok(())
 }
 
-pub fn navigate_directory(base_path: &Path, base_dir_name: &str, i18n: &I18n) -> Result<PathBuf> {
+/// Create `subdir` below a layer root, declaring every newly created directory
+/// as a module in its parent so the new tree is reachable.
+pub fn ensure_module_dir(root: &Path, subdir: &Path, policy: ModPolicy) -> Result<PathBuf> {
+    let mut plan = GenerationPlan::default();
+    let dir = plan.ensure_module_dir(root, subdir, policy)?;
+    plan.apply()?;
+    Ok(dir)
+}
+
+/// Create directory `name` in `parent` with an empty mod.rs and declare it in the parent module.
+pub fn create_module_dir(parent: &Path, name: &str, policy: ModPolicy) -> Result<PathBuf> {
+    ensure_module_dir(parent, Path::new(name), policy)
+}
+
+/// Whether `name` can be used as a module (and directory) name: a snake_case identifier
+/// that is neither `_` nor a keyword such as `type` or `crate`.
+pub fn is_valid_module_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_lowercase() || c == '_')
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        && syn::parse_str::<syn::Ident>(name).is_ok()
+}
+
+/// Entries of the directory navigation menu.
+enum NavEntry {
+    SameSubpath(PathBuf, String),
+    Done,
+    NewDirectory,
+    Search,
+    GoUp,
+    Enter(PathBuf),
+}
+
+/// Navigate below `base_path` and return the chosen subpath. `mirror` is the subpath
+/// picked for a previous layer (with that layer's name), offered first while at the root.
+pub fn navigate_directory(
+    base_path: &Path,
+    base_dir_name: &str,
+    mirror: Option<(&Path, &str)>,
+    policy: ModPolicy,
+    prompter: &dyn Prompter,
+    i18n: &I18n,
+) -> Result<PathBuf> {
     let mut current_path = base_path.to_path_buf();
+    let mirror = mirror.filter(|(subpath, _)| !subpath.as_os_str().is_empty());
     
     loop {
         // Get subdirectories
         let subdirs = get_subdirectories(&current_path)?;
-        
-        if subdirs.is_empty() {
-            // Show current path even when no subdirectories
-            let relative_path = current_path.strip_prefix(base_path)
-                .unwrap_or(Path::new(""))
-                .display()
-                .to_string();
-            let display_path = if relative_path.is_empty() {
-                format!("{}/", base_dir_name)
-            } else {
-                format!("{}/{}/", base_dir_name, relative_path)
-            };
-            
-            println!("\n{}: {}", i18n.current_path(), display_path);
-            println!("{}", i18n.no_subdirectories());
-            return Ok(current_path.strip_prefix(base_path)?.to_path_buf());
-        }
-
-        // Build options: "Done" + subdirectories
-        let mut options = vec![i18n.done_option()];
-        options.extend(subdirs.iter().map(|d| d.to_string_lossy().to_string()));
 
         // Show current path with base directory name
         let relative_path = current_path.strip_prefix(base_path)
@@ -53,24 +77,112 @@ pub fn navigate_directory(base_path: &Path, base_dir_name: &str, i18n: &I18n) ->
             format!("{}/{}/", base_dir_name, relative_path)
         };
         
-        println!("\n{}: {}", i18n.current_path(), display_path);
+        // Shown on stderr next to the prompts, keeping stdout for the report
+        eprintln!("\n{}: {}", i18n.current_path(), display_path);
+        if subdirs.is_empty() {
+            eprintln!("{}", i18n.no_subdirectories());
+        }
+
+        // Build options: "Same subpath" (at the root, when mirroring), "Done", "New directory", "Search" (when there is a tree to search),
+        // "Go up" (below the base) + subdirectories
+        let mut entries = Vec::new();
+        if let Some((subpath, layer)) = mirror.filter(|_| current_path == base_path) {
+            entries.push(NavEntry::SameSubpath(subpath.to_path_buf(), layer.to_string()));
+        }
+        entries.extend([NavEntry::Done, NavEntry::NewDirectory]);
+        if !get_subdirectories(base_path)?.is_empty() {
+            entries.push(NavEntry::Search);
+        }
+        if current_path != base_path {
+            entries.push(NavEntry::GoUp);
+        }
+        entries.extend(subdirs.into_iter().map(NavEntry::Enter));
+
+        let options: Vec<String> = entries
+            .iter()
+            .map(|entry| match entry {
+                NavEntry::SameSubpath(subpath, layer) => {
+                    i18n.same_subpath_option(layer, &format!("{}/", subpath.display()))
+                }
+                NavEntry::Done => i18n.done_option(),
+                NavEntry::NewDirectory => i18n.new_directory_option(),
+                NavEntry::Search => i18n.search_option(),
+                NavEntry::GoUp => i18n.go_up_option(),
+                NavEntry::Enter(dir) => dir.to_string_lossy().to_string(),
+            })
+            .collect();
 
-        let selection = Select::new()
-            .with_prompt(i18n.select_directory_prompt())
-            .items(&options)
-            .default(0)
-            .interact()?;
+        let selection = prompter.select(&i18n.select_directory_prompt(), &options, 0)?;
 
-        if selection == 0 {
+        match &entries[selection] {
+            // Mirror the previous layer, creating the directories when missing
+            NavEntry::SameSubpath(subpath, _) => {
+                ensure_module_dir(base_path, subpath, policy)?;
+                return Ok(subpath.clone());
+            }
             // User selected "Done"
-            return Ok(current_path.strip_prefix(base_path)?.to_path_buf());
-        } else {
+            NavEntry::Done => return Ok(current_path.strip_prefix(base_path)?.to_path_buf()),
+            NavEntry::NewDirectory => {
+                if let Some(dir) = prompt_new_directory(&current_path, policy, prompter, i18n)? {
+                    current_path = dir;
+                }
+            }
+            NavEntry::Search => {
+                if let Some(dir) = search_directory(base_path, prompter, i18n)? {
+                    current_path = base_path.join(dir);
+                }
+            }
+            NavEntry::GoUp => {
+                current_path.pop();
+            }
             // Navigate into selected subdirectory
-            current_path = current_path.join(&subdirs[selection - 1]);
+            NavEntry::Enter(dir) => current_path = current_path.join(dir),
         }
     }
 }
 
+/// Ask for a directory name and create it in `parent` once confirmed.
+fn prompt_new_directory(parent: &Path, policy: ModPolicy, prompter: &dyn Prompter, i18n: &I18n) -> Result<Option<PathBuf>> {
+    let validate = |input: &str| -> Result<(), String> {
+        if is_valid_module_name(input) {
+            Ok(())
+        } else {
+            Err(i18n.invalid_module_name())
+        }
+    };
+    let name = prompter.input(&i18n.new_directory_prompt(), false, Some(&validate))?;
+
+    let prompt = i18n.confirm_create_directory(&parent.join(&name).display().to_string());
+    if !prompter.confirm(&prompt, true)? {
+        return Ok(None);
+    }
+
+    let dir = create_module_dir(parent, &name, policy)?;
+    eprintln!("{}", i18n.file_created(&dir.display().to_string()));
+    Ok(Some(dir))
+}
+
+/// Fuzzy-search all directories below `base_path`; `None` when cancelled with Esc.
+fn search_directory(base_path: &Path, prompter: &dyn Prompter, i18n: &I18n) -> Result<Option<PathBuf>> {
+    let dirs = get_nested_directories(base_path)?;
+    let options: Vec<String> = dirs.iter().map(|dir| dir.display().to_string()).collect();
+
+    let selection = prompter.fuzzy_select(&i18n.search_prompt(), &options)?;
+    Ok(selection.map(|index| dirs[index].clone()))
+}
+
+/// All directories below `path`, relative to it, in depth-first sorted order.
+pub(crate) fn get_nested_directories(path: &Path) -> Result<Vec<PathBuf>> {
+    let mut dirs = Vec::new();
+    for entry in WalkDir::new(path).min_depth(1).sort_by_file_name() {
+        let entry = entry?;
+        if entry.file_type().is_dir() {
+            dirs.push(entry.path().strip_prefix(path)?.to_path_buf());
+        }
+    }
+    Ok(dirs)
+}
+
 fn get_subdirectories(path: &Path) -> Result<Vec<PathBuf>> {
     let mut subdirs = Vec::new();
     
@@ -90,28 +202,54 @@ fn get_subdirectories(path: &Path) -> Result<Vec<PathBuf>> {
     Ok(subdirs)
 }
 
-pub fn update_mod_file(dir_path: &Path, module_name: &str) -> Result<PathBuf> {
+/// mod.rs, else lib.rs or main.rs, else a new mod.rs in `dir_path`, given which files exist.
+pub(crate) fn mod_file_path(dir_path: &Path, exists: impl Fn(&Path) -> bool) -> PathBuf {
     let mod_file = dir_path.join("mod.rs");
-    let lib_file = dir_path.join("lib.rs");
-    let mod_line = format!("pub mod {};\n", module_name);
-    
-    if mod_file.exists() {
-        // mod.rs exists, add to it
-        let content = fs::read_to_string(&mod_file)?;
-        if !content.contains(&format!("pub mod {}", module_name)) {
-            fs::write(&mod_file, format!("{}{}", content, mod_line))?;
-        }
-        Ok(mod_file)
-    } else if lib_file.exists() {
-        // mod.rs doesn't exist but lib.rs exists, add to lib.rs
-        let content = fs::read_to_string(&lib_file)?;
-        if !content.contains(&format!("pub mod {}", module_name)) {
-            fs::write(&lib_file, format!("{}{}", content, mod_line))?;
+    if exists(&mod_file) {
+        return mod_file;
+    }
+    [dir_path.join("lib.rs"), dir_path.join("main.rs")]
+        .into_iter()
+        .find(|root| exists(root))
+        .unwrap_or(mod_file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ensure_module_dir_wires_new_directories() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = ensure_module_dir(root.path(), Path::new("admin/billing"), ModPolicy::default()).unwrap();
+
+        assert_eq!(dir, root.path().join("admin/billing"));
+        assert_eq!(fs::read_to_string(root.path().join("mod.rs")).unwrap(), "pub mod admin;\n");
+        assert_eq!(fs::read_to_string(root.path().join("admin/mod.rs")).unwrap(), "pub mod billing;\n");
+        assert_eq!(fs::read_to_string(dir.join("mod.rs")).unwrap(), "");
+    }
+
+    #[test]
+    fn test_get_nested_directories() {
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir_all(root.path().join("admin/user")).unwrap();
+        fs::create_dir_all(root.path().join("public")).unwrap();
+        fs::write(root.path().join("admin/mod.rs"), "").unwrap();
+
+        let dirs = get_nested_directories(root.path()).unwrap();
+        let expected: Vec<PathBuf> = ["admin", "admin/user", "public"].iter().map(PathBuf::from).collect();
+        assert_eq!(dirs, expected);
+    }
+
+    #[test]
+    fn test_is_valid_module_name() {
+        assert!(is_valid_module_name("billing_v2"));
+        assert!(!is_valid_module_name("2fa"));
+        assert!(!is_valid_module_name("Billing"));
+        assert!(!is_valid_module_name(""));
+        assert!(!is_valid_module_name("_"));
+        for keyword in ["mod", "type", "self", "crate", "super", "async"] {
+            assert!(!is_valid_module_name(keyword), "{}", keyword);
         }
-        Ok(lib_file)
-    } else {
-        // Neither exists, create new mod.rs
-        fs::write(&mod_file, mod_line)?;
-        Ok(mod_file)
     }
 }
@@ -0,0 +1,140 @@
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+use crate::manifest::{find_manifest, search_dirs};
+
+/// rustfmt config files, in the order rustfmt looks for them in each directory.
+const RUSTFMT_CONFIGS: [&str; 2] = ["rustfmt.toml", ".rustfmt.toml"];
+
+/// How generated source files are formatted before they are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Formatter {
+    /// rustfmt when it is installed, otherwise only trailing whitespace is removed.
+    /// Source rustfmt rejects is an error, which the generator reports and writes unformatted.
+    #[default]
+    Auto,
+    /// The `rustfmt` binary, honoring the project's rustfmt.toml
+    Rustfmt,
+    /// prettyplease in-process; drops the placeholder comments
+    Prettyplease,
+    /// Write templates as rendered
+    None,
+}
+
+impl Formatter {
+    /// Format `source`, which will be written to `path`.
+    pub fn format(self, source: &str, path: &Path) -> Result<String> {
+        let dir = path.parent().unwrap_or(Path::new("."));
+        match self {
+            Formatter::Auto if !rustfmt_available() => Ok(trim_trailing_whitespace(source)),
+            Formatter::Auto | Formatter::Rustfmt => {
+                rustfmt(source, dir).with_context(|| format!("failed to format {}", path.display()))
+            }
+            Formatter::Prettyplease => {
+                let file = syn::parse_file(source)
+                    .with_context(|| format!("failed to format {}", path.display()))?;
+                Ok(prettyplease::unparse(&file))
+            }
+            Formatter::None => Ok(source.to_string()),
+        }
+    }
+}
+
+/// Whether `rustfmt` runs, checked once per process.
+pub(crate) fn rustfmt_available() -> bool {
+    static AVAILABLE: OnceLock<bool> = OnceLock::new();
+    *AVAILABLE.get_or_init(|| {
+        Command::new("rustfmt")
+            .arg("--version")
+            .output()
+            .is_ok_and(|output| output.status.success())
+    })
+}
+
+/// Run rustfmt on `source` with the config and edition that apply to files in `dir`.
+fn rustfmt(source: &str, dir: &Path) -> Result<String> {
+    let mut command = Command::new("rustfmt");
+    command.args(["--emit", "stdout", "--edition", &edition(dir)]);
+    if let Some(config) = rustfmt_config(dir) {
+        command.arg("--config-path").arg(config);
+    }
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("failed to run rustfmt")?;
+    child
+        .stdin
+        .take()
+        .context("failed to open rustfmt stdin")?
+        .write_all(source.as_bytes())?;
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        bail!("rustfmt failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// The nearest rustfmt.toml at or above `dir`.
+fn rustfmt_config(dir: &Path) -> Option<PathBuf> {
+    search_dirs(dir)
+        .into_iter()
+        .flat_map(|ancestor| RUSTFMT_CONFIGS.iter().map(move |name| ancestor.join(name)))
+        .find(|config| config.is_file())
+}
+
+/// `package.edition` of the crate owning `dir`, defaulting to 2021.
+fn edition(dir: &Path) -> String {
+    find_manifest(dir)
+        .and_then(|manifest| fs::read_to_string(manifest).ok())
+        .and_then(|content| content.parse::<toml::Table>().ok())
+        .and_then(|manifest| manifest.get("package")?.get("edition")?.as_str().map(str::to_string))
+        .unwrap_or_else(|| "2021".to_string())
+}
+
+pub(crate) fn trim_trailing_whitespace(source: &str) -> String {
+    source.lines().map(str::trim_end).collect::<Vec<_>>().join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "use b::B;\nuse a::A;\n\npub fn f() {\n    // keep me\n    let x = 1;    \n    \n}\n";
+
+    #[test]
+    fn test_rustfmt_honors_project_config() {
+        if !rustfmt_available() {
+            return;
+        }
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("rustfmt.toml"), "hard_tabs = true\n").unwrap();
+
+        let formatted = Formatter::Rustfmt.format(SOURCE, &dir.path().join("src/f.rs")).unwrap();
+        assert_eq!(formatted, "use a::A;\nuse b::B;\n\npub fn f() {\n\t// keep me\n\tlet x = 1;\n}\n");
+    }
+
+    #[test]
+    fn test_prettyplease_and_fallback() {
+        let path = Path::new("f.rs");
+        let formatted = Formatter::Prettyplease.format(SOURCE, path).unwrap();
+        assert_eq!(formatted, "use b::B;\nuse a::A;\npub fn f() {\n    let x = 1;\n}\n");
+
+        assert_eq!(
+            trim_trailing_whitespace(SOURCE),
+            "use b::B;\nuse a::A;\n\npub fn f() {\n    // keep me\n    let x = 1;\n\n}\n"
+        );
+        assert!(Formatter::Prettyplease.format("fn (", path).is_err());
+        if rustfmt_available() {
+            assert!(Formatter::Auto.format("fn (", path).is_err());
+        }
+    }
+}
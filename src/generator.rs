@@ -1,61 +1,230 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use serde::Serialize;
 use std::fs;
-use std::path::Path;
-use crate::i18n::I18n;
-use crate::file_ops::update_mod_file;
+use std::path::{Path, PathBuf};
+use crate::i18n::{I18n, Language};
 
-pub fn generate_controller(
-    dir_path: &Path,
-    file_name: &str,
-    method_name: &str,
-    service_file_name: &str,
-    i18n: &I18n,
-) -> Result<()> {
-    // Create directory if it doesn't exist
-    fs::create_dir_all(dir_path)?;
+/// A field rendered into a generated request/response struct.
+#[derive(Debug, Clone)]
+pub struct StructField {
+    pub name: String,
+    pub ty: String,
+    /// Original wire name, when `rename_all = "camelCase"` would not reproduce it
+    pub rename: Option<String>,
+}
 
-    let file_path = dir_path.join(format!("{}.rs", file_name));
-    
-    // Generate controller content
-    let content = generate_controller_template(file_name, method_name, service_file_name);
-    
-    fs::write(&file_path, content)?;
-    println!("{}", i18n.file_created(&file_path.display().to_string()));
+/// The HTTP route a handler serves, rendered as a `#[utoipa::path]` attribute.
+#[derive(Debug, Clone, Serialize)]
+pub struct Route {
+    pub method: String,
+    pub path: String,
+    pub tag: Option<String>,
+}
 
-    // Update mod.rs or lib.rs
-    let updated_file = update_mod_file(dir_path, file_name)?;
-    println!("{}", i18n.mod_updated(&updated_file.display().to_string()));
+/// Request/response structs emitted into a dedicated DTO module.
+#[derive(Debug, Clone)]
+pub struct DtoModule {
+    /// Full `use` path of the DTO module
+    pub import: String,
+    /// `use` path for the layer the controller calls into, which may be another crate
+    pub callee_import: String,
+    /// Struct name prefix, e.g. `User` for `UserRequest`/`UserResponse`
+    pub struct_name: String,
+}
 
-    Ok(())
+/// Optional content for a generated file beyond the default placeholders.
+#[derive(Debug, Clone, Default)]
+pub struct TemplateExtras {
+    /// Imports needed by the request/response struct fields
+    pub imports: Vec<String>,
+    pub request_fields: Vec<StructField>,
+    pub response_fields: Vec<StructField>,
+    pub route: Option<Route>,
+    /// When set, the controller and the service it calls import their
+    /// request/response structs from this module instead of the controller file
+    pub dto: Option<DtoModule>,
+    /// Language of the placeholder comments in generated code
+    pub comment_language: Language,
 }
 
-pub fn generate_service(
-    dir_path: &Path,
-    file_name: &str,
-    method_name: &str,
-    i18n: &I18n,
-) -> Result<()> {
-    // Create directory if it doesn't exist
-    fs::create_dir_all(dir_path)?;
+impl TemplateExtras {
+    /// Extras for the layer at `index`, given the extras of the outermost layer:
+    /// the layer it calls into shares the DTO module, deeper layers get nothing.
+    pub fn for_layer(&self, index: usize) -> TemplateExtras {
+        match index {
+            0 => self.clone(),
+            1 => TemplateExtras {
+                dto: self.dto.clone().map(|dto| DtoModule {
+                    import: dto.callee_import.clone(),
+                    ..dto
+                }),
+                comment_language: self.comment_language,
+                ..TemplateExtras::default()
+            },
+            _ => TemplateExtras {
+                comment_language: self.comment_language,
+                ..TemplateExtras::default()
+            },
+        }
+    }
+}
 
-    let file_path = dir_path.join(format!("{}.rs", file_name));
-    
-    // Generate service content
-    let content = generate_service_template(method_name);
-    
-    fs::write(&file_path, content)?;
-    println!("{}", i18n.file_created(&file_path.display().to_string()));
+/// Built-in templates, or a user template file with `{{placeholder}}` variables.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Template {
+    Controller,
+    Service,
+    Repository,
+    Dto,
+    Entity,
+    File(PathBuf),
+}
 
-    // Update mod.rs or lib.rs
-    let updated_file = update_mod_file(dir_path, file_name)?;
-    println!("{}", i18n.mod_updated(&updated_file.display().to_string()));
+impl Template {
+    /// Resolve a template name from the project config; anything that is not a
+    /// built-in name is treated as a template file relative to `base_dir`.
+    pub fn from_name(name: &str, base_dir: &Path) -> Self {
+        match name {
+            "controller" | "handler" => Template::Controller,
+            "service" => Template::Service,
+            "repository" | "dao" => Template::Repository,
+            "dto" | "model" => Template::Dto,
+            "entity" => Template::Entity,
+            path => Template::File(base_dir.join(path)),
+        }
+    }
+
+    /// Packages the rendered file imports, as named in `[dependencies]`.
+    pub fn dependencies(&self) -> &'static [&'static str] {
+        match self {
+            Template::Controller => &["axum", "serde", "serde_json", "utoipa"],
+            Template::Service | Template::Repository | Template::Entity => &["sea-orm"],
+            Template::Dto => &["serde", "utoipa"],
+            Template::File(_) => &[],
+        }
+    }
+
+    /// Expression a file in the layer above uses to reach a file rendered from this template.
+    /// `args` starts with the connection expression.
+    fn call_expr(&self, module: &str, method_name: &str, struct_name: &str, args: &[&str]) -> String {
+        match self {
+            Template::Dto => format!("{}::{}::default()", module, struct_name),
+            Template::Entity => format!("{}::Entity::find().all({}).await?", module, args[0]),
+            _ => format!("{}::{}({}).await?", module, method_name, args.join(", ")),
+        }
+    }
 
-    Ok(())
+    /// Statement form of [`Template::call_expr`] for layers that discard the result.
+    fn call_stmt(&self, module: &str, method_name: &str, struct_name: &str, args: &[&str]) -> String {
+        let expr = self.call_expr(module, method_name, struct_name, args);
+        match self {
+            Template::Dto => format!("let _dto = {};", expr),
+            Template::Entity => format!("let _models = {};", expr),
+            _ => format!("{};", expr),
+        }
+    }
+
+    /// Extra imports the layer above needs for [`Template::call_expr`] to compile.
+    fn call_imports(&self) -> &'static [&'static str] {
+        match self {
+            Template::Entity => &["sea_orm::EntityTrait"],
+            _ => &[],
+        }
+    }
+}
+
+/// The next layer down that a generated file calls into.
+#[derive(Debug, Clone)]
+pub struct NextLayer {
+    pub template: Template,
+    /// Full `use` path of the next layer's module
+    pub import: String,
+    pub file_name: String,
+    pub method_name: String,
+}
+
+impl NextLayer {
+    fn module(&self) -> &str {
+        self.import.rsplit("::").next().unwrap_or(&self.import)
+    }
+
+    fn call_expr(&self, args: &[&str]) -> String {
+        self.template.call_expr(self.module(), &self.method_name, &to_pascal_case(&self.file_name), args)
+    }
+
+    fn call_stmt(&self, args: &[&str]) -> String {
+        self.template.call_stmt(self.module(), &self.method_name, &to_pascal_case(&self.file_name), args)
+    }
+
+    fn imports(&self) -> String {
+        std::iter::once(self.import.as_str())
+            .chain(self.template.call_imports().iter().copied())
+            .map(|import| format!("use {};\n", import))
+            .collect()
+    }
+}
+
+pub fn render_template(
+    template: &Template,
+    file_name: &str,
+    method_name: &str,
+    next: Option<&NextLayer>,
+    extras: &TemplateExtras,
+) -> Result<String> {
+    let comments = I18n::new(extras.comment_language);
+    Ok(match template {
+        Template::Controller => generate_controller_template(file_name, method_name, next, extras),
+        Template::Service => generate_callable_template(method_name, &comments.comment_service_logic(), next, extras),
+        Template::Repository => generate_callable_template(method_name, &comments.comment_data_access_logic(), next, extras),
+        Template::Dto => generate_dto_template(file_name, &comments.comment_fields()),
+        Template::Entity => generate_entity_template(file_name),
+        Template::File(path) => {
+            let source = fs::read_to_string(path)
+                .with_context(|| format!("failed to read template {}", path.display()))?;
+            render_file_template(&source, file_name, method_name, next)
+        }
+    })
 }
 
-fn generate_controller_template(file_name: &str, method_name: &str, service_file_name: &str) -> String {
+fn generate_controller_template(
+    file_name: &str,
+    method_name: &str,
+    next: Option<&NextLayer>,
+    extras: &TemplateExtras,
+) -> String {
     // Convert file_name to PascalCase for struct names
     let struct_name = to_pascal_case(file_name);
+
+    let next_imports = next.map(NextLayer::imports).unwrap_or_default();
+    let call = match (next, &extras.dto) {
+        (Some(next), Some(_)) => format!("let res = {};", next.call_expr(&["&state.conn", "&domain", "body"])),
+        (Some(next), None) => format!("let res = {};", next.call_expr(&["&state.conn", "&domain"])),
+        (None, _) => format!(
+            "// {}\n    let res = serde_json::Value::Null;",
+            I18n::new(extras.comment_language).comment_handler_logic()
+        ),
+    };
+    let structs = match &extras.dto {
+        Some(dto) => {
+            // The response struct is only named by the route attribute
+            let names = if extras.route.is_some() {
+                format!("{{{0}Request, {0}Response}}", dto.struct_name)
+            } else {
+                format!("{}Request", dto.struct_name)
+            };
+            format!("use {}::{};\n\n", dto.import, names)
+        }
+        None => format!(
+            "{}\nuse serde::{{Deserialize, Serialize}};\nuse utoipa::ToSchema;\n\n{}\n",
+            render_imports(&extras.imports),
+            render_structs(&struct_name, extras, false)
+        ),
+    };
+    let route = extras
+        .route
+        .as_ref()
+        .map(|route| render_route(route, &struct_name))
+        .unwrap_or_default();
     
     format!(r#"use axum::extract::Query;
 use axum::extract::State;
@@ -67,54 +236,338 @@ use public::public::{{
     origin_display, public_list_response, ApiResponse, AppState, PublicListResponse, QueryParams,
     ReturnResult,
 }};
-use service_admin_chinese_hsk::{};
+{}{}{}pub async fn {}(
+    Extension(domain): Extension<String>,
+    Query(query): Query<QueryParams>,
+    State(state): State<AppState>,
+    SafeJson(body): SafeJson<{}Request>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {{
+    {}
+    
+    let result = origin_display(query, res, &domain);
+    
+    Ok(ApiResponse::success(result).to_json())
+}}
+"#, next_imports, structs, route, method_name, struct_name, call)
+}
 
-use serde::{{Deserialize, Serialize}};
-use utoipa::ToSchema;
+fn render_imports(imports: &[String]) -> String {
+    imports.iter().map(|import| format!("use {};\n", import)).collect()
+}
+
+/// Render the `{Name}Request`/`{Name}Response` pair. Structs in a DTO module
+/// also derive `Debug`/`Serialize` and `Default` so services and tests can build them.
+fn render_structs(struct_name: &str, extras: &TemplateExtras, reusable: bool) -> String {
+    let (request_derive, response_derive) = if reusable {
+        ("Debug, Serialize, Deserialize, ToSchema", "Debug, Default, Serialize, Deserialize, ToSchema")
+    } else {
+        ("Deserialize, ToSchema", "Debug, Serialize, Deserialize, ToSchema")
+    };
+    let comments = I18n::new(extras.comment_language);
 
-#[derive(Deserialize, ToSchema)]
+    format!(r#"#[derive({})]
 #[serde(rename_all = "camelCase")]
 pub struct {}Request {{
-    // Add your request fields here
+{}
 }}
 
-#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[derive({})]
 #[serde(rename_all = "camelCase")]
 pub struct {}Response {{
-    // Add your response fields here
+{}
 }}
+"#,
+        request_derive,
+        struct_name,
+        render_fields(&extras.request_fields, &format!("// {}", comments.comment_request_fields())),
+        response_derive,
+        struct_name,
+        render_fields(&extras.response_fields, &format!("// {}", comments.comment_response_fields())),
+    )
+}
 
-pub async fn {}(
-    Extension(domain): Extension<String>,
-    Query(query): Query<QueryParams>,
-    State(state): State<AppState>,
-    SafeJson(body): SafeJson<{}Request>,
-) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {{
-    let res = {}::{}(&state.conn, &domain).await?;
-    
-    let result = origin_display(query, res, &domain);
-    
-    Ok(ApiResponse::success(result).to_json())
+/// Render the request/response structs of a controller as their own module.
+pub fn render_dto_module(file_name: &str, extras: &TemplateExtras) -> String {
+    format!(
+        "use serde::{{Deserialize, Serialize}};\nuse utoipa::ToSchema;\n{}\n{}",
+        render_imports(&extras.imports),
+        render_structs(&to_pascal_case(file_name), extras, true)
+    )
+}
+
+/// Render struct fields, falling back to a placeholder comment when there are none.
+pub(crate) fn render_fields(fields: &[StructField], placeholder: &str) -> String {
+    if fields.is_empty() {
+        return format!("    {}", placeholder);
+    }
+
+    fields
+        .iter()
+        .map(|field| match &field.rename {
+            Some(rename) => format!(
+                "    #[serde(rename = \"{}\")]\n    pub {}: {},",
+                rename, field.name, field.ty
+            ),
+            None => format!("    pub {}: {},", field.name, field.ty),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn render_route(route: &Route, struct_name: &str) -> String {
+    let mut args = vec![
+        route.method.to_lowercase(),
+        format!("path = \"{}\"", route.path),
+    ];
+    if let Some(tag) = &route.tag {
+        args.push(format!("tag = \"{}\"", tag));
+    }
+    args.push(format!("request_body = {}Request", struct_name));
+    args.push(format!("responses((status = 200, body = {}Response))", struct_name));
+
+    format!("#[utoipa::path(\n    {}\n)]\n", args.join(",\n    "))
+}
+
+/// Packages a generated integration test needs besides those of the controller.
+pub const TEST_DEPENDENCIES: [&str; 3] = ["tokio", "tower", "http-body-util"];
+
+/// Integration test sending a sample `{Name}Request` to the route of the handler
+/// `method_name` in `module`, which the test crate reaches as `krate`.
+pub fn render_test(
+    module: &str,
+    krate: &str,
+    subdir: &Path,
+    file_name: &str,
+    method_name: &str,
+    extras: &TemplateExtras,
+) -> String {
+    let in_crate = |import: &str| match import.strip_prefix("crate::") {
+        Some(rest) => format!("{}::{}", krate, rest),
+        None => import.to_string(),
+    };
+    let request = match &extras.dto {
+        Some(dto) => format!("{}::{}Request", in_crate(&dto.import), dto.struct_name),
+        None => format!("{}::{}Request", in_crate(module), to_pascal_case(file_name)),
+    };
+    let struct_name = request.rsplit("::").next().unwrap_or(&request).to_string();
+    // Without a route, assume the one the TUI suggests for the file
+    let (method, path) = match &extras.route {
+        Some(route) => (route.method.to_uppercase(), route.path.clone()),
+        None => {
+            let mut segments: Vec<String> = subdir.iter().map(|s| s.to_string_lossy().into_owned()).collect();
+            segments.push(file_name.to_string());
+            ("POST".to_string(), format!("/{}", segments.join("/")))
+        }
+    };
+    let body: Vec<String> = extras
+        .request_fields
+        .iter()
+        .map(|field| {
+            let name = field.rename.clone().unwrap_or_else(|| to_camel_case(&field.name));
+            format!("        \"{}\": {}", name, sample_value(&field.ty))
+        })
+        .collect();
+    let body = if body.is_empty() { "{}".to_string() } else { format!("{{\n{}\n    }}", body.join(",\n")) };
+
+    let handler = format!("{}::{}", in_crate(module), method_name);
+    let comments = I18n::new(extras.comment_language);
+
+    format!(r#"use axum::body::Body;
+use axum::http::{{Request, StatusCode}};
+use axum::routing::{};
+use axum::{{Extension, Router}};
+use http_body_util::BodyExt;
+use serde_json::json;
+use tower::ServiceExt;
+use {};
+
+use crate::support;
+
+#[tokio::test]
+async fn {}_returns_api_response() {{
+    let Some(state) = support::state().await else {{
+        eprintln!("{}");
+        return;
+    }};
+    let app = Router::new()
+        .route("{}", {}({}))
+        .layer(Extension("localhost".to_string()))
+        .with_state(state);
+
+    let body = json!({});
+    // {}
+    let _: {} = serde_json::from_value(body.clone()).unwrap();
+
+    let request = Request::builder()
+        .method("{}")
+        .uri("{}")
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    support::assert_api_response(&json);
+}}
+"#,
+        method.to_lowercase(),
+        request,
+        method_name,
+        comments.test_skipped(method_name),
+        path,
+        method.to_lowercase(),
+        handler,
+        body,
+        comments.comment_sample_request(&struct_name),
+        struct_name,
+        method,
+        sample_path(&path),
+    )
+}
+
+/// Support module shared by the generated tests: the state the handlers run
+/// with and the expected shape of an `ApiResponse`.
+pub fn render_test_support(comment_language: Language) -> String {
+    let comments = I18n::new(comment_language);
+    format!(r#"use public::public::AppState;
+use serde_json::Value;
+
+pub async fn state() -> Option<AppState> {{
+    // {}
+    None
+}}
+
+pub fn assert_api_response(json: &Value) {{
+    // {}
+    assert!(json.is_object(), "not an ApiResponse: {{}}", json);
+    assert!(json.get("data").is_some(), "ApiResponse without data: {{}}", json);
 }}
-"#, service_file_name, struct_name, struct_name, method_name, struct_name, service_file_name, method_name)
+"#, comments.comment_test_state(), comments.comment_api_response_shape())
 }
 
-fn generate_service_template(method_name: &str) -> String {
+/// JSON literal of a sample value for a field of Rust type `ty`; `Option`s get a value too.
+fn sample_value(ty: &str) -> &'static str {
+    let ty = ty.trim();
+    if let Some(inner) = ty.strip_prefix("Option<").and_then(|rest| rest.strip_suffix('>')) {
+        return sample_value(inner);
+    }
+    let outer = ty.split('<').next().unwrap_or(ty).rsplit("::").next().unwrap_or(ty);
+    match outer {
+        "String" | "str" | "&str" => "\"string\"",
+        "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32" | "u64" | "u128" | "usize" => "0",
+        "f32" | "f64" => "0.0",
+        "bool" => "false",
+        "Vec" | "HashSet" | "BTreeSet" => "[]",
+        "Value" => "null",
+        _ => "{}",
+    }
+}
+
+/// `path` with `{id}` or `:id` parameters filled in.
+fn sample_path(path: &str) -> String {
+    path.split('/')
+        .map(|segment| {
+            if segment.starts_with(':') || (segment.starts_with('{') && segment.ends_with('}')) {
+                "1"
+            } else {
+                segment
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Template shared by layers exposing an async function (service, repository).
+fn generate_callable_template(
+    method_name: &str,
+    comment: &str,
+    next: Option<&NextLayer>,
+    extras: &TemplateExtras,
+) -> String {
+    let (mut imports, body) = match next {
+        Some(next) => (
+            next.imports(),
+            format!("// {}\n    {}\n", comment, next.call_stmt(&["conn", "domain"])),
+        ),
+        None => (String::new(), format!("// {}\n    ", comment)),
+    };
+
+    let (body_param, return_type, return_value) = match &extras.dto {
+        Some(dto) => {
+            imports.insert_str(0, &format!("use {}::{{{1}Request, {1}Response}};\n", dto.import, dto.struct_name));
+            (
+                format!("\n    body: {}Request,", dto.struct_name),
+                format!("{}Response", dto.struct_name),
+                format!("{}Response::default()", dto.struct_name),
+            )
+        }
+        None => (String::new(), "()".to_string(), "()".to_string()),
+    };
+
     format!(r#"use sea_orm::DatabaseConnection;
 use error_crate::api_error::ApiResult;
-
+{}
 pub async fn {}(
     conn: &DatabaseConnection,
-    domain: &str,
-) -> ApiResult<()> {{
-    // Add your service logic here
-    
-    Ok(())
+    domain: &str,{}
+) -> ApiResult<{}> {{
+    {}
+    Ok({})
 }}
-"#, method_name)
+"#, imports, method_name, body_param, return_type, body, return_value)
 }
 
-fn to_pascal_case(s: &str) -> String {
+fn generate_dto_template(file_name: &str, comment: &str) -> String {
+    format!(r#"use serde::{{Deserialize, Serialize}};
+use utoipa::ToSchema;
+
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct {} {{
+    // {}
+}}
+"#, to_pascal_case(file_name), comment)
+}
+
+fn generate_entity_template(file_name: &str) -> String {
+    format!(r#"use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "{}")]
+pub struct Model {{
+    #[sea_orm(primary_key)]
+    pub id: i64,
+}}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {{}}
+
+impl ActiveModelBehavior for ActiveModel {{}}
+"#, file_name)
+}
+
+/// Substitute `{{name}}` placeholders in a user-provided template.
+fn render_file_template(source: &str, file_name: &str, method_name: &str, next: Option<&NextLayer>) -> String {
+    let mut vars = vec![
+        ("file_name", file_name.to_string()),
+        ("method_name", method_name.to_string()),
+        ("struct_name", to_pascal_case(file_name)),
+    ];
+    if let Some(next) = next {
+        vars.push(("next_import", next.import.clone()));
+        vars.push(("next_module", next.module().to_string()));
+        vars.push(("next_method", next.method_name.clone()));
+        vars.push(("next_call", next.call_expr(&["conn", "domain"])));
+    }
+
+    vars.iter().fold(source.to_string(), |out, (name, value)| {
+        out.replace(&format!("{{{{{}}}}}", name), value)
+    })
+}
+
+pub(crate) fn to_pascal_case(s: &str) -> String {
     s.split('_')
         .map(|word| {
             let mut chars = word.chars();
@@ -126,6 +579,41 @@ fn to_pascal_case(s: &str) -> String {
         .collect()
 }
 
+/// `snake_case` to the `camelCase` serde's `rename_all` produces.
+pub(crate) fn to_camel_case(s: &str) -> String {
+    let pascal = to_pascal_case(s);
+    let mut chars = pascal.chars();
+    match chars.next() {
+        None => String::new(),
+        Some(first) => first.to_lowercase().collect::<String>() + chars.as_str(),
+    }
+}
+
+/// Convert camelCase, kebab-case or free text into a snake_case identifier.
+pub(crate) fn to_snake_case(s: &str) -> String {
+    let chars: Vec<char> = s.chars().collect();
+    let mut out = String::new();
+
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if !out.is_empty() && !out.ends_with('_') {
+                out.push('_');
+            }
+            continue;
+        }
+        if c.is_uppercase() && i > 0 && !out.is_empty() && !out.ends_with('_') {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            if prev.is_lowercase() || prev.is_ascii_digit() || (prev.is_uppercase() && next_is_lower) {
+                out.push('_');
+            }
+        }
+        out.extend(c.to_lowercase());
+    }
+
+    out.trim_end_matches('_').to_string()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -136,5 +624,68 @@ mod tests {
         assert_eq!(to_pascal_case("test"), "Test");
         assert_eq!(to_pascal_case("my_api_controller"), "MyApiController");
     }
+
+    #[test]
+    fn test_service_calls_next_layer() {
+        let next = NextLayer {
+            template: Template::Repository,
+            import: "crate::repository::admin::user_repository".to_string(),
+            file_name: "user_repository".to_string(),
+            method_name: "find_user".to_string(),
+        };
+        let content = render_template(&Template::Service, "user_service", "get_user", Some(&next), &TemplateExtras::default()).unwrap();
+        assert!(content.contains("use crate::repository::admin::user_repository;"));
+        assert!(content.contains("user_repository::find_user(conn, domain).await?;"));
+    }
+
+    #[test]
+    fn test_dto_module_shared_by_controller_and_service() {
+        let extras = TemplateExtras {
+            dto: Some(DtoModule {
+                import: "crate::dto::admin::user".to_string(),
+                callee_import: "crate::dto::admin::user".to_string(),
+                struct_name: "User".to_string(),
+            }),
+            ..TemplateExtras::default()
+        };
+        let next = NextLayer {
+            template: Template::Service,
+            import: "crate::service::admin::user_service".to_string(),
+            file_name: "user_service".to_string(),
+            method_name: "get_user".to_string(),
+        };
+
+        let controller = render_template(&Template::Controller, "user", "get_user", Some(&next), &extras.for_layer(0)).unwrap();
+        assert!(controller.contains("use crate::dto::admin::user::UserRequest;"));
+        assert!(!controller.contains("pub struct UserRequest"));
+        assert!(controller.contains("user_service::get_user(&state.conn, &domain, body)"));
+
+        let service = render_template(&Template::Service, "user_service", "get_user", None, &extras.for_layer(1)).unwrap();
+        assert!(service.contains("use crate::dto::admin::user::{UserRequest, UserResponse};"));
+        assert!(service.contains("body: UserRequest,"));
+        assert!(service.contains(") -> ApiResult<UserResponse> {"));
+    }
+
+    #[test]
+    fn test_comments_follow_comment_language() {
+        let english = render_template(&Template::Controller, "user", "get_user", None, &TemplateExtras::default()).unwrap();
+        assert!(english.contains("    // Add your request fields here\n"));
+        assert!(english.contains("    // Add your handler logic here\n"));
+
+        let extras = TemplateExtras {
+            comment_language: Language::from_str("zh"),
+            ..TemplateExtras::default()
+        };
+        let service = render_template(&Template::Service, "user_service", "get_user", None, &extras.for_layer(2)).unwrap();
+        assert!(service.contains("    // 在此编写业务逻辑\n"));
+    }
+
+    #[test]
+    fn test_to_snake_case() {
+        assert_eq!(to_snake_case("createUser"), "create_user");
+        assert_eq!(to_snake_case("Admin User"), "admin_user");
+        assert_eq!(to_snake_case("HTTPServer-config"), "http_server_config");
+        assert_eq!(to_snake_case("already_snake"), "already_snake");
+    }
 }
 
@@ -1,15 +1,84 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::sync::OnceLock;
+
+/// Message catalogs embedded at compile time, one TOML table of `key = "message"` per locale.
+/// Adding a locale only takes a new file and an entry here.
+const CATALOGS: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.toml")),
+    ("zh", include_str!("../locales/zh.toml")),
+    ("zh-TW", include_str!("../locales/zh-TW.toml")),
+    ("ja", include_str!("../locales/ja.toml")),
+    ("ko", include_str!("../locales/ko.toml")),
+];
+
+type Catalog = BTreeMap<String, String>;
+
+/// A UI locale with a shipped catalog, identified by its code, e.g. `zh-TW`.
 #[derive(Debug, Clone, Copy, PartialEq)]
-pub enum Language {
-    En,
-    Zh,
+pub struct Language(&'static str);
+
+impl Default for Language {
+    fn default() -> Self {
+        Self::EN
+    }
 }
 
+/// Subtags of a `zh` locale that select Traditional Chinese.
+const TRADITIONAL_CHINESE: [&str; 4] = ["hant", "tw", "hk", "mo"];
+
 impl Language {
-    pub fn from_str(s: &str) -> Self {
-        match s.to_lowercase().as_str() {
-            "zh" => Language::Zh,
-            _ => Language::En,
+    pub const EN: Language = Language("en");
+
+    /// Every shipped locale, in catalog order.
+    pub fn all() -> impl Iterator<Item = Language> {
+        CATALOGS.iter().map(|(code, _)| Language(code))
+    }
+
+    /// The locale matching `s` case-insensitively, with `_` accepted for `-`; a region
+    /// without its own catalog falls back to the base language (`ja-JP` → `ja`).
+    /// Traditional Chinese (`zh-Hant`, `zh-HK`, `zh-MO`) uses the `zh-TW` catalog.
+    pub fn find(s: &str) -> Option<Self> {
+        let normalized = s.trim().replace('_', "-");
+        let matching = |code: &str| Language::all().find(|lang| lang.0.eq_ignore_ascii_case(code));
+        let mut subtags = normalized.split('-');
+        let base = subtags.next()?;
+        if base.eq_ignore_ascii_case("zh") {
+            let subtags: Vec<String> = subtags.map(str::to_ascii_lowercase).collect();
+            let traditional = subtags.iter().any(|tag| TRADITIONAL_CHINESE.contains(&tag.as_str()));
+            if traditional && !subtags.iter().any(|tag| tag == "hans") {
+                return matching("zh-TW");
+            }
         }
+        matching(&normalized).or_else(|| matching(base))
+    }
+
+    /// Locale from the first of `LC_ALL`, `LC_MESSAGES` and `LANG` that is set.
+    pub fn from_env() -> Option<Self> {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| env::var(name).ok())
+            .find(|locale| !locale.is_empty())
+            .and_then(|locale| Self::from_locale(&locale))
+    }
+
+    /// Parse a POSIX locale such as `zh_CN.UTF-8`; `C` and `POSIX` give `None`.
+    pub fn from_locale(locale: &str) -> Option<Self> {
+        Self::find(locale.split(['.', '@']).next()?)
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Self {
+        Self::find(s).unwrap_or(Self::EN)
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.0
+    }
+
+    /// Name of the language in itself, e.g. `日本語`.
+    pub fn name(&self) -> String {
+        I18n::new(*self).message("language_name", &[])
     }
 }
 
@@ -22,189 +91,423 @@ impl I18n {
         Self { lang }
     }
 
+    /// Shown before a language is chosen, so always in English.
     pub fn invalid_language(input: &str) -> String {
-        format!("Invalid language: {}. Please choose 'en' or 'zh'.", input)
+        let languages = Language::all().map(|lang| lang.code()).collect::<Vec<_>>().join(", ");
+        I18n::new(Language::EN).message("invalid_language", &[("input", input), ("languages", &languages)])
     }
 
     pub fn select_language_prompt() -> String {
-        "Please select a language / 请选择语言".to_string()
+        I18n::new(Language::EN).message("select_language_prompt", &[])
+    }
+
+    /// Message `key` with `{name}` placeholders replaced by `args`, falling back to
+    /// English when the current locale lacks the key, and to the key itself after that.
+    fn message(&self, key: &str, args: &[(&str, &str)]) -> String {
+        let template = self
+            .lookup(key)
+            .or_else(|| catalog(Language::EN).get(key).map(String::as_str))
+            .unwrap_or(key);
+        interpolate(template, args)
+    }
+
+    fn lookup(&self, key: &str) -> Option<&'static str> {
+        catalog(self.lang).get(key).map(String::as_str)
     }
 
     pub fn target_not_found(&self) -> String {
-        match self.lang {
-            Language::En => "Target directory not found".to_string(),
-            Language::Zh => "目标目录不存在".to_string(),
-        }
+        self.message("target_not_found", &[])
     }
 
     pub fn directories_not_found(&self, dirs: &[&String]) -> String {
         let dir_list = dirs.iter().map(|s| s.as_str()).collect::<Vec<_>>().join(", ");
-        match self.lang {
-            Language::En => format!("Directory not found: {}", dir_list),
-            Language::Zh => format!("未找到目录: {}", dir_list),
-        }
+        self.message("directories_not_found", &[("dirs", &dir_list)])
     }
 
     pub fn create_option(&self) -> String {
-        match self.lang {
-            Language::En => "Create".to_string(),
-            Language::Zh => "新建".to_string(),
-        }
+        self.message("create_option", &[])
     }
 
     pub fn cancel_option(&self) -> String {
-        match self.lang {
-            Language::En => "Cancel".to_string(),
-            Language::Zh => "放弃".to_string(),
-        }
+        self.message("cancel_option", &[])
     }
 
     pub fn select_action_prompt(&self) -> String {
-        match self.lang {
-            Language::En => "Please select an action".to_string(),
-            Language::Zh => "请选择操作".to_string(),
-        }
+        self.message("select_action_prompt", &[])
     }
 
     pub fn directories_created(&self) -> String {
-        match self.lang {
-            Language::En => "Directories created successfully".to_string(),
-            Language::Zh => "目录创建成功".to_string(),
-        }
+        self.message("directories_created", &[])
     }
 
     pub fn task_cancelled(&self, dirs: &[&String]) -> String {
         let dir_list = dirs.iter().map(|s| s.as_str()).collect::<Vec<_>>().join(", ");
-        match self.lang {
-            Language::En => format!("User cancelled creation of: {}", dir_list),
-            Language::Zh => format!("用户放弃创建: {}", dir_list),
-        }
+        self.message("task_cancelled", &[("dirs", &dir_list)])
     }
 
     pub fn create_directories_option(&self, dirs: &[&String]) -> String {
         let dir_list = dirs.iter().map(|s| s.as_str()).collect::<Vec<_>>().join(", ");
-        match self.lang {
-            Language::En => format!("Create {} directories", dir_list),
-            Language::Zh => format!("创建 {} 目录", dir_list),
-        }
+        self.message("create_directories_option", &[("dirs", &dir_list)])
     }
 
     pub fn cancel_and_exit_option(&self) -> String {
-        match self.lang {
-            Language::En => "Cancel and exit, task ended".to_string(),
-            Language::Zh => "放弃创建，本次任务结束".to_string(),
-        }
+        self.message("cancel_and_exit_option", &[])
     }
 
     pub fn task_ended(&self) -> String {
-        match self.lang {
-            Language::En => "Task ended".to_string(),
-            Language::Zh => "本次任务已经结束".to_string(),
-        }
+        self.message("task_ended", &[])
     }
 
-    pub fn navigate_controller(&self) -> String {
-        match self.lang {
-            Language::En => "=== Navigate Controller Directory ===".to_string(),
-            Language::Zh => "=== 导航控制器目录 ===".to_string(),
-        }
+    /// Localized display name of a layer; unknown layers keep their configured name.
+    pub fn layer_name(&self, name: &str) -> String {
+        let key = format!("layer_{}", name);
+        self.lookup(&key).map_or_else(|| name.to_string(), |_| self.message(&key, &[]))
     }
 
-    pub fn navigate_service(&self) -> String {
-        match self.lang {
-            Language::En => "=== Navigate Service Directory ===".to_string(),
-            Language::Zh => "=== 导航服务目录 ===".to_string(),
-        }
+    pub fn navigate_layer(&self, name: &str) -> String {
+        let layer = self.layer_name(name);
+        self.message("navigate_layer", &[("layer", &layer), ("Layer", &capitalize(&layer))])
     }
 
     pub fn current_path(&self) -> String {
-        match self.lang {
-            Language::En => "Current path".to_string(),
-            Language::Zh => "当前路径".to_string(),
-        }
+        self.message("current_path", &[])
     }
 
     pub fn done_option(&self) -> String {
-        match self.lang {
-            Language::En => "✓ Done (create file here)".to_string(),
-            Language::Zh => "✓ 选好了（在此创建文件）".to_string(),
-        }
+        self.message("done_option", &[])
     }
 
     pub fn select_directory_prompt(&self) -> String {
-        match self.lang {
-            Language::En => "Select a directory or choose 'Done'".to_string(),
-            Language::Zh => "选择目录或选择'选好了'".to_string(),
-        }
+        self.message("select_directory_prompt", &[])
     }
 
-    pub fn controller_file_prompt(&self) -> String {
-        match self.lang {
-            Language::En => "Enter controller file name (without .rs)".to_string(),
-            Language::Zh => "请输入控制器文件名称（不含.rs）".to_string(),
-        }
-    }
-
-    pub fn service_file_prompt(&self) -> String {
-        match self.lang {
-            Language::En => "Enter service file name (without .rs)".to_string(),
-            Language::Zh => "请输入服务文件名称（不含.rs）".to_string(),
-        }
+    pub fn layer_file_prompt(&self, name: &str) -> String {
+        let layer = self.layer_name(name);
+        self.message("layer_file_prompt", &[("layer", &layer)])
     }
 
     pub fn method_name_prompt(&self) -> String {
-        match self.lang {
-            Language::En => "Enter method name (leave empty to use file name)".to_string(),
-            Language::Zh => "请输入方法名（如果不填写，则与文件名相同）".to_string(),
-        }
+        self.message("method_name_prompt", &[])
     }
 
-    pub fn add_service_suffix_option(&self) -> String {
-        match self.lang {
-            Language::En => "Add '_service' suffix to file name".to_string(),
-            Language::Zh => "自动给文件名加 _service 后缀".to_string(),
-        }
+    pub fn add_suffix_option(&self, suffix: &str) -> String {
+        self.message("add_suffix_option", &[("suffix", suffix)])
     }
 
-    pub fn skip_option(&self) -> String {
-        match self.lang {
-            Language::En => "Skip".to_string(),
-            Language::Zh => "跳过".to_string(),
-        }
+    pub fn suffix_prompt(&self, name: &str) -> String {
+        let layer = self.layer_name(name);
+        self.message("suffix_prompt", &[("layer", &layer)])
     }
 
-    pub fn service_suffix_prompt(&self) -> String {
-        match self.lang {
-            Language::En => "Add service suffix?".to_string(),
-            Language::Zh => "是否添加服务后缀？".to_string(),
-        }
+    pub fn skip_option(&self) -> String {
+        self.message("skip_option", &[])
     }
 
     pub fn generation_complete(&self) -> String {
-        match self.lang {
-            Language::En => "✓ Code generation completed successfully!".to_string(),
-            Language::Zh => "✓ 代码生成完成！".to_string(),
-        }
+        self.message("generation_complete", &[])
     }
 
     pub fn file_created(&self, path: &str) -> String {
-        match self.lang {
-            Language::En => format!("✓ Created: {}", path),
-            Language::Zh => format!("✓ 已创建: {}", path),
-        }
+        self.message("file_created", &[("path", path)])
     }
 
     pub fn mod_updated(&self, path: &str) -> String {
-        match self.lang {
-            Language::En => format!("✓ Updated: {}", path),
-            Language::Zh => format!("✓ 已更新: {}", path),
-        }
+        self.message("mod_updated", &[("path", path)])
     }
 
     pub fn no_subdirectories(&self) -> String {
-        match self.lang {
-            Language::En => "No subdirectories found, creating file in current directory".to_string(),
-            Language::Zh => "未找到子目录，将在当前目录创建文件".to_string(),
+        self.message("no_subdirectories", &[])
+    }
+
+    pub fn same_subpath_option(&self, layer: &str, subpath: &str) -> String {
+        let layer = self.layer_name(layer);
+        self.message("same_subpath_option", &[("layer", &layer), ("subpath", subpath)])
+    }
+
+    pub fn new_directory_option(&self) -> String {
+        self.message("new_directory_option", &[])
+    }
+
+    pub fn search_option(&self) -> String {
+        self.message("search_option", &[])
+    }
+
+    pub fn search_prompt(&self) -> String {
+        self.message("search_prompt", &[])
+    }
+
+    pub fn go_up_option(&self) -> String {
+        self.message("go_up_option", &[])
+    }
+
+    pub fn new_directory_prompt(&self) -> String {
+        self.message("new_directory_prompt", &[])
+    }
+
+    pub fn invalid_module_name(&self) -> String {
+        self.message("invalid_module_name", &[])
+    }
+
+    pub fn confirm_create_directory(&self, path: &str) -> String {
+        self.message("confirm_create_directory", &[("path", path)])
+    }
+
+    pub fn file_exists_skipped(&self, path: &str) -> String {
+        self.message("file_exists_skipped", &[("path", path)])
+    }
+
+    pub fn file_unformatted(&self, path: &str, error: &str) -> String {
+        self.message("file_unformatted", &[("path", path), ("error", error)])
+    }
+
+    pub fn openapi_imported(&self, count: usize) -> String {
+        self.message("openapi_imported", &[("count", &count.to_string())])
+    }
+
+    pub fn select_layer_crate(&self, name: &str) -> String {
+        let layer = self.layer_name(name);
+        self.message("select_layer_crate", &[("layer", &layer)])
+    }
+
+    pub fn missing_dependencies(&self, manifest: &str, names: &[&str]) -> String {
+        let list = names.join(", ");
+        self.message("missing_dependencies", &[("manifest", manifest), ("names", &list)])
+    }
+
+    pub fn add_dependencies_option(&self) -> String {
+        self.message("add_dependencies_option", &[])
+    }
+
+    pub fn tui_directories_title(&self, name: &str) -> String {
+        let layer = self.layer_name(name);
+        self.message("tui_directories_title", &[("layer", &layer), ("Layer", &capitalize(&layer))])
+    }
+
+    pub fn mod_files_sorted(&self) -> String {
+        self.message("mod_files_sorted", &[])
+    }
+
+    pub fn undeclared_module(&self, path: &str, mod_file: &str) -> String {
+        self.message("undeclared_module", &[("path", path), ("mod_file", mod_file)])
+    }
+
+    pub fn dangling_declaration(&self, mod_file: &str, module: &str) -> String {
+        self.message("dangling_declaration", &[("mod_file", mod_file), ("module", module)])
+    }
+
+    pub fn missing_item(&self, path: &str, module: &str, item: &str) -> String {
+        self.message("missing_item", &[("path", path), ("module", module), ("item", item)])
+    }
+
+    pub fn unreferenced_module(&self, path: &str, layer: &str) -> String {
+        self.message("unreferenced_module", &[("path", path), ("layer", &self.layer_name(layer))])
+    }
+
+    pub fn file_removed(&self, path: &str) -> String {
+        self.message("file_removed", &[("path", path)])
+    }
+
+    pub fn files_to_remove(&self) -> String {
+        self.message("files_to_remove", &[])
+    }
+
+    pub fn confirm_remove(&self) -> String {
+        self.message("confirm_remove", &[])
+    }
+
+    pub fn remove_needs_yes(&self) -> String {
+        self.message("remove_needs_yes", &[])
+    }
+
+    pub fn remaining_references(&self) -> String {
+        self.message("remaining_references", &[])
+    }
+
+    pub fn file_moved(&self, from: &str, to: &str) -> String {
+        self.message("file_moved", &[("from", from), ("to", to)])
+    }
+
+    pub fn dry_run_notice(&self) -> String {
+        self.message("dry_run_notice", &[])
+    }
+
+    pub fn list_module(&self) -> String {
+        self.message("list_module", &[])
+    }
+
+    pub fn list_handler(&self) -> String {
+        self.message("list_handler", &[])
+    }
+
+    pub fn list_extractors(&self) -> String {
+        self.message("list_extractors", &[])
+    }
+
+    pub fn list_service(&self) -> String {
+        self.message("list_service", &[])
+    }
+
+    pub fn list_route(&self) -> String {
+        self.message("list_route", &[])
+    }
+
+    pub fn no_endpoints(&self, path: &str) -> String {
+        self.message("no_endpoints", &[("path", path)])
+    }
+
+    pub fn check_passed(&self) -> String {
+        self.message("check_passed", &[])
+    }
+
+    pub fn check_failed(&self, count: usize) -> String {
+        self.message("check_failed", &[("count", &count.to_string())])
+    }
+
+    pub fn tui_form_title(&self) -> String {
+        self.message("tui_form_title", &[])
+    }
+
+    pub fn tui_preview_title(&self) -> String {
+        self.message("tui_preview_title", &[])
+    }
+
+    pub fn tui_help(&self) -> String {
+        self.message("tui_help", &[])
+    }
+
+    pub fn file_name_label(&self) -> String {
+        self.message("file_name_label", &[])
+    }
+
+    pub fn method_name_label(&self) -> String {
+        self.message("method_name_label", &[])
+    }
+
+    pub fn suffix_label(&self, suffix: &str) -> String {
+        self.message("suffix_label", &[("suffix", suffix)])
+    }
+
+    pub fn http_method_label(&self) -> String {
+        self.message("http_method_label", &[])
+    }
+
+    pub fn none_option(&self) -> String {
+        self.message("none_option", &[])
+    }
+
+    pub fn missing_file_name(&self, name: &str) -> String {
+        let layer = self.layer_name(name);
+        self.message("missing_file_name", &[("layer", &layer)])
+    }
+
+    pub fn comment_handler_logic(&self) -> String {
+        self.message("comment_handler_logic", &[])
+    }
+
+    pub fn comment_service_logic(&self) -> String {
+        self.message("comment_service_logic", &[])
+    }
+
+    pub fn comment_data_access_logic(&self) -> String {
+        self.message("comment_data_access_logic", &[])
+    }
+
+    pub fn comment_request_fields(&self) -> String {
+        self.message("comment_request_fields", &[])
+    }
+
+    pub fn comment_response_fields(&self) -> String {
+        self.message("comment_response_fields", &[])
+    }
+
+    pub fn comment_fields(&self) -> String {
+        self.message("comment_fields", &[])
+    }
+
+    pub fn comment_sample_request(&self, struct_name: &str) -> String {
+        self.message("comment_sample_request", &[("struct_name", struct_name)])
+    }
+
+    pub fn comment_test_state(&self) -> String {
+        self.message("comment_test_state", &[])
+    }
+
+    pub fn test_skipped(&self, name: &str) -> String {
+        self.message("test_skipped", &[("name", name)])
+    }
+
+    pub fn comment_api_response_shape(&self) -> String {
+        self.message("comment_api_response_shape", &[])
+    }
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
+        None => String::new(),
+    }
+}
+
+fn catalog(lang: Language) -> &'static Catalog {
+    static PARSED: OnceLock<BTreeMap<&str, Catalog>> = OnceLock::new();
+    let parsed = PARSED.get_or_init(|| {
+        CATALOGS
+            .iter()
+            .map(|(code, source)| {
+                let catalog = toml::from_str(source)
+                    .unwrap_or_else(|err| panic!("invalid {} message catalog: {}", code, err));
+                (*code, catalog)
+            })
+            .collect()
+    });
+    &parsed[lang.code()]
+}
+
+/// Replace `{name}` placeholders with the matching argument.
+fn interpolate(template: &str, args: &[(&str, &str)]) -> String {
+    args.iter().fold(template.to_string(), |text, (name, value)| {
+        text.replace(&format!("{{{}}}", name), value)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalogs_define_every_key() {
+        let english = catalog(Language::EN);
+        for lang in Language::all() {
+            let keys = catalog(lang);
+            let missing: Vec<&String> = english.keys().filter(|key| !keys.contains_key(*key)).collect();
+            let unknown: Vec<&String> = keys.keys().filter(|key| !english.contains_key(*key)).collect();
+            assert!(missing.is_empty(), "{:?} catalog is missing {:?}", lang, missing);
+            assert!(unknown.is_empty(), "{:?} catalog has keys unknown to English: {:?}", lang, unknown);
         }
     }
+
+    #[test]
+    fn test_find_language() {
+        assert_eq!(Language::find("zh_tw").map(|lang| lang.code()), Some("zh-TW"));
+        assert_eq!(Language::find("ja-JP").map(|lang| lang.code()), Some("ja"));
+        assert_eq!(Language::find("fr"), None);
+        assert_eq!(Language::from_locale("zh_CN.UTF-8").map(|lang| lang.code()), Some("zh"));
+        assert_eq!(Language::from_locale("zh_TW.UTF-8").map(|lang| lang.code()), Some("zh-TW"));
+        for traditional in ["zh_HK", "zh-Hant", "zh_MO.UTF-8", "zh-Hant-CN"] {
+            assert_eq!(Language::from_locale(traditional).map(|lang| lang.code()), Some("zh-TW"), "{}", traditional);
+        }
+        assert_eq!(Language::find("zh-Hans-HK").map(|lang| lang.code()), Some("zh"));
+        assert_eq!(Language::from_locale("C.UTF-8"), None);
+        assert_eq!(Language::from_str("ko").name(), "한국어");
+    }
+
+    #[test]
+    fn test_message_interpolates_arguments() {
+        let i18n = I18n::new(Language::from_str("zh"));
+        assert_eq!(i18n.file_created("a.rs"), "✓ 已创建: a.rs");
+        assert_eq!(i18n.layer_name("dao"), "仓储");
+        assert_eq!(i18n.layer_name("gateway"), "gateway");
+        assert_eq!(I18n::new(Language::EN).navigate_layer("service"), "=== Navigate Service Directory ===");
+    }
 }
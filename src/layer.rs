@@ -0,0 +1,199 @@
+use anyhow::{bail, Result};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use crate::generator::{to_pascal_case, DtoModule, NextLayer, Template};
+use crate::mod_file::{ModOrder, ModPolicy, ModStyle};
+use crate::workspace::Member;
+
+/// One architectural layer of the target project, e.g. controller or service.
+///
+/// Layers are ordered from the outermost (called by the router) to the innermost;
+/// each generated file calls into the file generated for the next layer.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Layer {
+    /// Display name, also the default template name
+    pub name: String,
+    /// Directory of the layer, relative to the target directory
+    pub dir: String,
+    /// Built-in template name or path to a template file relative to the target
+    pub template: Option<String>,
+    /// Naming suffix offered for file names, e.g. `_service`
+    #[serde(default)]
+    pub suffix: String,
+    /// Path the layer above imports this layer's modules from, e.g. a separate crate.
+    /// Defaults to `crate::<dir>::<subdir>`; ignored when the layer lives in a workspace member.
+    pub import_path: Option<String>,
+    /// Workspace member crate holding the layer; asked for interactively when unset
+    #[serde(rename = "crate")]
+    pub krate: Option<String>,
+    /// How generated modules are declared in their parent mod.rs
+    #[serde(default)]
+    pub mod_style: ModStyle,
+}
+
+/// Where a layer lives on disk and, in a workspace, which member crate owns it.
+#[derive(Debug, Clone)]
+pub struct LayerLocation {
+    pub path: PathBuf,
+    pub member: Option<Member>,
+}
+
+/// Choices made while navigating one layer.
+#[derive(Debug, Clone, Default)]
+pub struct LayerSelection {
+    pub subdir: PathBuf,
+    pub file_name: String,
+    pub method_name: String,
+}
+
+impl Layer {
+    pub fn new(name: &str, dir: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            dir: dir.to_string(),
+            template: None,
+            suffix: String::new(),
+            import_path: None,
+            krate: None,
+            mod_style: ModStyle::default(),
+        }
+    }
+
+    pub fn mod_policy(&self, order: ModOrder) -> ModPolicy {
+        ModPolicy { order, style: self.mod_style }
+    }
+
+    pub fn template(&self, target: &Path) -> Template {
+        Template::from_name(self.template.as_deref().unwrap_or(&self.name), target)
+    }
+
+    /// `use` path of a module generated in `subdir` of this layer at `location`,
+    /// as seen from a file in the crate of `from`.
+    pub fn module_import_at(
+        &self,
+        location: &LayerLocation,
+        from: &LayerLocation,
+        subdir: &Path,
+        file_name: &str,
+    ) -> String {
+        match (&location.member, &from.member) {
+            (Some(member), Some(from_member)) => {
+                member.module_path(&location.path.join(subdir), file_name, from_member)
+            }
+            _ => self.module_import(subdir, file_name),
+        }
+    }
+
+    /// `use` path the layer above imports to call a module generated in `subdir`:
+    /// the module itself, or its parent when the layer re-exports module items there.
+    pub fn caller_import(&self, location: &LayerLocation, from: &LayerLocation, subdir: &Path, file_name: &str) -> String {
+        let import = self.module_import_at(location, from, subdir, file_name);
+        match import.rsplit_once("::") {
+            Some((parent, _)) if self.mod_style.reexports() => parent.to_string(),
+            _ => import,
+        }
+    }
+
+    /// `use` path of a module generated in `subdir` of this layer.
+    pub fn module_import(&self, subdir: &Path, file_name: &str) -> String {
+        match &self.import_path {
+            Some(prefix) => format!("{}::{}", prefix, file_name),
+            None => {
+                let mut segments = vec!["crate".to_string()];
+                segments.extend(self.dir.split(['/', '\\']).filter(|s| !s.is_empty()).map(str::to_string));
+                segments.extend(subdir.iter().map(|s| s.to_string_lossy().into_owned()));
+                segments.push(file_name.to_string());
+                segments.join("::")
+            }
+        }
+    }
+}
+
+/// The controller/service pair used when the project config declares no layers.
+pub fn default_layers(controller_dir: &str, service_dir: &str) -> Vec<Layer> {
+    vec![
+        Layer::new("controller", controller_dir),
+        Layer {
+            suffix: "_service".to_string(),
+            import_path: Some("service_admin_chinese_hsk".to_string()),
+            ..Layer::new("service", service_dir)
+        },
+    ]
+}
+
+/// DTOs live with the layer the controller calls into, so that both can import them: in its
+/// workspace member, inside its directory when it is imported as another crate
+/// (`import_path`), or else below the target.
+pub fn dto_location(dto_dir: &str, target: &Path, layers: &[Layer], locations: &[LayerLocation]) -> Result<LayerLocation> {
+    if dto_dir.split(['/', '\\']).all(|segment| segment.trim().is_empty()) {
+        bail!("dto_dir must not be empty");
+    }
+    let owner = locations.get(1).or(locations.first());
+    Ok(match (owner.and_then(|l| l.member.as_ref()), owner) {
+        (Some(member), _) => LayerLocation {
+            path: member.src_dir.join(dto_dir),
+            member: Some(member.clone()),
+        },
+        (None, Some(owner)) if dto_owner(layers).is_some_and(|layer| layer.import_path.is_some()) => LayerLocation {
+            path: owner.path.join(dto_dir),
+            member: None,
+        },
+        _ => LayerLocation {
+            path: target.join(dto_dir),
+            member: None,
+        },
+    })
+}
+
+/// DTO module generated for a controller in `subdir`, importable from the first two layers.
+pub fn dto_module(
+    dto_dir: &str,
+    dto_location: &LayerLocation,
+    layers: &[Layer],
+    locations: &[LayerLocation],
+    subdir: &Path,
+    file_name: &str,
+) -> DtoModule {
+    let dto_layer = Layer::new("dto", dto_dir);
+    let callee = locations.get(1).unwrap_or(&locations[0]);
+    let mut import = dto_layer.module_import_at(dto_location, &locations[0], subdir, file_name);
+    // In the crate of an owner imported through `import_path`, the controller reaches it by that path
+    if dto_location.member.is_none() {
+        if let Some(prefix) = dto_owner(layers).and_then(|layer| layer.import_path.as_deref()) {
+            import = import.replacen("crate", prefix, 1);
+        }
+    }
+    DtoModule {
+        import,
+        callee_import: dto_layer.module_import_at(dto_location, callee, subdir, file_name),
+        struct_name: to_pascal_case(file_name),
+    }
+}
+
+/// The layer owning the DTOs: the one the controller calls into.
+fn dto_owner(layers: &[Layer]) -> Option<&Layer> {
+    layers.get(1).or(layers.first())
+}
+
+/// How the file generated for layer `index` calls into the next layer, if any.
+pub fn next_layer(
+    target: &Path,
+    layers: &[Layer],
+    locations: &[LayerLocation],
+    selections: &[LayerSelection],
+    index: usize,
+) -> Option<NextLayer> {
+    let next_layer = layers.get(index + 1)?;
+    let next_selection = &selections[index + 1];
+    Some(NextLayer {
+        template: next_layer.template(target),
+        import: next_layer.caller_import(
+            &locations[index + 1],
+            &locations[index],
+            &next_selection.subdir,
+            &next_selection.file_name,
+        ),
+        file_name: next_selection.file_name.clone(),
+        method_name: next_selection.method_name.clone(),
+    })
+}
@@ -2,3 +2,21 @@ pub mod cli;
 pub mod i18n;
 pub mod generator;
 pub mod file_ops;
+pub mod openapi;
+pub mod config;
+pub mod layer;
+pub mod workspace;
+pub mod manifest;
+pub mod tui;
+pub mod prompt;
+pub mod plan;
+pub mod report;
+pub mod format;
+pub mod mod_file;
+pub mod check;
+pub mod remove;
+pub mod refactor;
+pub mod list;
+
+#[cfg(test)]
+mod test_support;
@@ -0,0 +1,214 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use syn::punctuated::Punctuated;
+use syn::visit::{self, Visit};
+use crate::check::{function_references, layer_modules, source_files};
+use crate::generator::Route;
+use crate::mod_file::source_tree;
+use crate::plan::Generator;
+
+/// HTTP methods accepted as the first argument of `#[utoipa::path]` and as axum routing functions.
+const METHODS: [&str; 7] = ["get", "post", "put", "delete", "patch", "head", "options"];
+
+/// One handler function of the outermost layer.
+#[derive(Debug, Clone, Serialize)]
+pub struct Endpoint {
+    /// `use` path of the module defining the handler
+    pub module: String,
+    pub function: String,
+    pub path: PathBuf,
+    /// Argument types, e.g. `State` and `Json`
+    pub extractors: Vec<String>,
+    /// Functions of the next layer the handler calls, e.g. `user_service::create`
+    pub services: Vec<String>,
+    pub route: Option<Route>,
+}
+
+/// Every public function in the outermost layer, with what it extracts, calls and serves.
+pub fn list(generator: &Generator) -> Result<Vec<Endpoint>> {
+    let locations = generator.resolve_locations()?;
+    let (Some(layer), Some(location)) = (generator.layers.first(), locations.first()) else {
+        return Ok(Vec::new());
+    };
+    let callee = match (generator.layers.get(1), locations.get(1)) {
+        (Some(next), Some(next_location)) => Some(layer_modules(next, next_location, location)?),
+        _ => None,
+    };
+    let routes = router_routes(&generator.target);
+
+    let mut endpoints = Vec::new();
+    for path in source_files(&location.path) {
+        let Ok(file) = syn::parse_file(&fs::read_to_string(&path)?) else {
+            continue;
+        };
+        let subdir = path.parent().and_then(|dir| dir.strip_prefix(&location.path).ok()).unwrap_or(Path::new(""));
+        let name = path.file_stem().unwrap_or_default().to_string_lossy();
+        let module = layer.module_import_at(location, location, subdir, &name);
+
+        for item in &file.items {
+            let syn::Item::Fn(function) = item else {
+                continue;
+            };
+            if !matches!(function.vis, syn::Visibility::Public(_)) {
+                continue;
+            }
+            let handler = function.sig.ident.to_string();
+            let services = match &callee {
+                Some(callee) => function_references(&file, function, callee)
+                    .into_iter()
+                    .map(|(module, item)| format!("{}::{}", module.rsplit("::").next().unwrap_or(&module), item))
+                    .collect(),
+                None => Vec::new(),
+            };
+            let route = function.attrs.iter().find_map(path_attribute).or_else(|| {
+                let qualified = format!("{}::{}", name, handler);
+                routes.iter().find(|(target, _)| *target == qualified || *target == handler).map(|(_, route)| route.clone())
+            });
+            endpoints.push(Endpoint {
+                module: module.clone(),
+                function: handler,
+                path: path.clone(),
+                extractors: function.sig.inputs.iter().filter_map(extractor).collect(),
+                services,
+                route,
+            });
+        }
+    }
+    Ok(endpoints)
+}
+
+/// Name of the type an argument is extracted as, e.g. `Query` for `Query(query): Query<QueryParams>`.
+fn extractor(input: &syn::FnArg) -> Option<String> {
+    let syn::FnArg::Typed(argument) = input else {
+        return None;
+    };
+    match argument.ty.as_ref() {
+        syn::Type::Path(path) => path.path.segments.last().map(|segment| segment.ident.to_string()),
+        syn::Type::Reference(_) => Some("&".to_string()),
+        _ => None,
+    }
+}
+
+/// The route of a `#[utoipa::path(post, path = "/users", tag = "admin")]` attribute.
+fn path_attribute(attribute: &syn::Attribute) -> Option<Route> {
+    let segments: Vec<String> = attribute.path().segments.iter().map(|segment| segment.ident.to_string()).collect();
+    if segments.last().map(String::as_str) != Some("path") {
+        return None;
+    }
+    let arguments = attribute
+        .parse_args_with(Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated)
+        .ok()?;
+    let mut method = None;
+    let mut path = None;
+    let mut tag = None;
+    for argument in arguments {
+        match argument {
+            syn::Meta::Path(name) if name.get_ident().is_some_and(|ident| METHODS.contains(&ident.to_string().as_str())) => {
+                method = name.get_ident().map(ToString::to_string);
+            }
+            syn::Meta::NameValue(value) => {
+                let syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(literal), .. }) = &value.value else {
+                    continue;
+                };
+                if value.path.is_ident("path") {
+                    path = Some(literal.value());
+                } else if value.path.is_ident("tag") {
+                    tag = Some(literal.value());
+                }
+            }
+            _ => {}
+        }
+    }
+    Some(Route { method: method?, path: path?, tag })
+}
+
+/// Routes registered with `.route("/users", post(user::create_user))` anywhere below `root`,
+/// keyed by the last two segments of the handler path.
+fn router_routes(root: &Path) -> Vec<(String, Route)> {
+    let mut routes = RouterRoutes::default();
+    let sources = source_tree(root)
+        .map(|entry| entry.into_path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "rs"));
+    for path in sources {
+        let Some(file) = fs::read_to_string(&path).ok().and_then(|content| syn::parse_file(&content).ok()) else {
+            continue;
+        };
+        routes.visit_file(&file);
+    }
+    routes.found
+}
+
+#[derive(Default)]
+struct RouterRoutes {
+    found: Vec<(String, Route)>,
+}
+
+impl<'ast> Visit<'ast> for RouterRoutes {
+    fn visit_expr_method_call(&mut self, call: &'ast syn::ExprMethodCall) {
+        if call.method == "route" && call.args.len() == 2 {
+            let path = match &call.args[0] {
+                syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(literal), .. }) => Some(literal.value()),
+                _ => None,
+            };
+            if let (Some(path), syn::Expr::Call(method_call)) = (path, &call.args[1]) {
+                self.add(&path, method_call);
+            }
+        }
+        visit::visit_expr_method_call(self, call);
+    }
+}
+
+impl RouterRoutes {
+    /// Record `post(handler)`, and each step of a `get(a).post(b)` chain.
+    fn add(&mut self, path: &str, call: &syn::ExprCall) {
+        let (syn::Expr::Path(method), Some(syn::Expr::Path(handler))) = (call.func.as_ref(), call.args.first()) else {
+            return;
+        };
+        let Some(method) = method.path.segments.last().map(|segment| segment.ident.to_string()) else {
+            return;
+        };
+        if !METHODS.contains(&method.as_str()) {
+            return;
+        }
+        let segments: Vec<String> = handler.path.segments.iter().map(|segment| segment.ident.to_string()).collect();
+        let target = segments[segments.len().saturating_sub(2)..].join("::");
+        self.found.push((target, Route { method, path: path.to_string(), tag: None }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::write_tree;
+
+    #[test]
+    fn test_list_handlers_with_services_and_routes() {
+        let root = tempfile::tempdir().unwrap();
+        write_tree(
+            root.path(),
+            &[
+                (
+                    "controller/admin/user.rs",
+                    "use service_admin_chinese_hsk::user_service;\n\n#[utoipa::path(post, path = \"/admin/users\", tag = \"admin\", responses((status = 200)))]\npub async fn create_user(State(state): State<AppState>, Json(body): Json<UserRequest>) {\n    user_service::create(&state.conn).await;\n}\n\npub async fn delete_user(Path(id): Path<i64>) {}\n\nfn helper() {}\n",
+                ),
+                ("service/admin/user_service.rs", "pub async fn create() {}\n"),
+                ("router.rs", "fn router() -> Router {\n    Router::new().route(\"/admin/users/:id\", delete(user::delete_user))\n}\n"),
+            ],
+        );
+
+        let endpoints = list(&Generator::new(root.path()).unwrap()).unwrap();
+        assert_eq!(endpoints.len(), 2);
+        assert_eq!(endpoints[0].module, "crate::controller::admin::user");
+        assert_eq!(endpoints[0].function, "create_user");
+        assert_eq!(endpoints[0].extractors, vec!["State", "Json"]);
+        assert_eq!(endpoints[0].services, vec!["user_service::create"]);
+        let route = endpoints[0].route.as_ref().unwrap();
+        assert_eq!((route.method.as_str(), route.path.as_str(), route.tag.as_deref()), ("post", "/admin/users", Some("admin")));
+
+        assert_eq!(endpoints[1].services, Vec::<String>::new());
+        let route = endpoints[1].route.as_ref().unwrap();
+        assert_eq!((route.method.as_str(), route.path.as_str()), ("delete", "/admin/users/:id"));
+    }
+}
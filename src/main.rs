@@ -1,11 +1,6 @@
-mod cli;
-mod i18n;
-mod generator;
-mod file_ops;
-
 use anyhow::Result;
 use clap::Parser;
-use cli::Cli;
+use laygen::cli::Cli;
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
@@ -0,0 +1,243 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml_edit::{value, Array, DocumentMut, InlineTable, Item, Table, Value};
+
+/// A Cargo.toml opened for format-preserving edits.
+pub struct Manifest {
+    pub path: PathBuf,
+    doc: DocumentMut,
+}
+
+impl Manifest {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let doc = content
+            .parse::<DocumentMut>()
+            .with_context(|| format!("invalid manifest {}", path.display()))?;
+        Ok(Self { path: path.to_path_buf(), doc })
+    }
+
+    /// Whether `[dependencies]` has `name`, directly or through `package = "..."`.
+    pub fn has_dependency(&self, name: &str) -> bool {
+        self.has_in("dependencies", name)
+    }
+
+    /// Whether `name` is available to tests, from `[dependencies]` or `[dev-dependencies]`.
+    pub fn has_dev_dependency(&self, name: &str) -> bool {
+        self.has_dependency(name) || self.has_in("dev-dependencies", name)
+    }
+
+    /// Package name from `[package]`.
+    pub fn package_name(&self) -> Option<&str> {
+        self.doc.get("package").and_then(|package| package.get("name")).and_then(Item::as_str)
+    }
+
+    /// Whether the package has a library target: a `[lib]` table or the default src/lib.rs.
+    pub fn has_lib(&self) -> bool {
+        self.doc.contains_key("lib") || self.path.parent().is_some_and(|dir| dir.join("src/lib.rs").is_file())
+    }
+
+    fn has_in(&self, table: &str, name: &str) -> bool {
+        let Some(deps) = self.doc.get(table).and_then(Item::as_table_like) else {
+            return false;
+        };
+
+        deps.iter().any(|(key, item)| {
+            let package = item
+                .as_table_like()
+                .and_then(|t| t.get("package"))
+                .and_then(Item::as_str)
+                .unwrap_or(key);
+            same_crate(key, name) || same_crate(package, name)
+        })
+    }
+
+    /// Whether the manifest declares `[workspace.dependencies] name`.
+    pub fn has_workspace_dependency(&self, name: &str) -> bool {
+        self.doc
+            .get("workspace")
+            .and_then(|w| w.get("dependencies"))
+            .and_then(Item::as_table_like)
+            .is_some_and(|deps| deps.iter().any(|(key, _)| same_crate(key, name)))
+    }
+
+    pub fn add_dependency(&mut self, name: &str, item: Item) {
+        self.add_to("dependencies", name, item);
+    }
+
+    pub fn add_dev_dependency(&mut self, name: &str, item: Item) {
+        self.add_to("dev-dependencies", name, item);
+    }
+
+    fn add_to(&mut self, table: &str, name: &str, item: Item) {
+        let deps = self
+            .doc
+            .entry(table)
+            .or_insert_with(|| Item::Table(Table::new()));
+        if let Some(deps) = deps.as_table_like_mut() {
+            deps.insert(name, item);
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        fs::write(&self.path, self.doc.to_string())?;
+        Ok(())
+    }
+}
+
+/// Cargo normalizes `-` and `_` in crate names.
+fn same_crate(a: &str, b: &str) -> bool {
+    a.replace('-', "_") == b.replace('-', "_")
+}
+
+/// The nearest Cargo.toml with a `[package]` at or above `dir`.
+pub fn find_manifest(dir: &Path) -> Option<PathBuf> {
+    search_dirs(dir)
+        .into_iter()
+        .map(|ancestor| ancestor.join("Cargo.toml"))
+        .find(|manifest| has_table(manifest, "package"))
+}
+
+/// The workspace root manifest above a member manifest, if any.
+pub fn find_workspace_manifest(member_manifest: &Path) -> Option<PathBuf> {
+    search_dirs(member_manifest.parent()?)
+        .into_iter()
+        .skip(1)
+        .map(|ancestor| ancestor.join("Cargo.toml"))
+        .find(|manifest| has_table(manifest, "workspace"))
+}
+
+/// Whether `manifest` parses and has a top-level `table`, e.g. `package`.
+fn has_table(manifest: &Path, table: &str) -> bool {
+    fs::read_to_string(manifest)
+        .ok()
+        .and_then(|content| content.parse::<DocumentMut>().ok())
+        .is_some_and(|doc| doc.contains_key(table))
+}
+
+/// `dir` and its ancestors, nearest first. A relative `dir` such as `.` is followed by the
+/// ancestors of its absolute path, so the search continues above the working directory.
+pub(crate) fn search_dirs(dir: &Path) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = dir.ancestors().map(Path::to_path_buf).collect();
+    if dir.is_relative() {
+        // `dir` may not exist yet; resolve its nearest existing ancestor
+        let existing = dir.ancestors().find_map(|ancestor| {
+            let ancestor = if ancestor.as_os_str().is_empty() { Path::new(".") } else { ancestor };
+            let rest = dir.strip_prefix(ancestor).unwrap_or(Path::new(""));
+            Some(fs::canonicalize(ancestor).ok()?.join(rest))
+        });
+        dirs.extend(existing.iter().flat_map(|absolute| absolute.ancestors()).map(Path::to_path_buf));
+    }
+    dirs
+}
+
+/// Version and features used when a required dependency is not configured.
+pub fn default_dependency(name: &str) -> Item {
+    match name {
+        "serde" => {
+            let mut table = InlineTable::new();
+            table.insert("version", "1.0".into());
+            table.insert("features", Value::Array(Array::from_iter(["derive"])));
+            value(table)
+        }
+        "serde_json" => value("1.0"),
+        "tokio" => {
+            let mut table = InlineTable::new();
+            table.insert("version", "1".into());
+            table.insert("features", Value::Array(Array::from_iter(["macros", "rt-multi-thread"])));
+            value(table)
+        }
+        "tower" => {
+            let mut table = InlineTable::new();
+            table.insert("version", "0.5".into());
+            table.insert("features", Value::Array(Array::from_iter(["util"])));
+            value(table)
+        }
+        "http-body-util" => value("0.1"),
+        "axum" => value("0.8"),
+        "utoipa" => value("5"),
+        "sea-orm" | "sea_orm" => value("1"),
+        _ => value("*"),
+    }
+}
+
+/// Dependency entry `{ path = "..." }`.
+pub fn path_dependency(path: &Path) -> Item {
+    let mut table = InlineTable::new();
+    table.insert("path", path.display().to_string().into());
+    value(table)
+}
+
+/// Dependency entry `{ workspace = true }`.
+pub fn workspace_dependency() -> Item {
+    let mut table = InlineTable::new();
+    table.insert("workspace", true.into());
+    value(table)
+}
+
+/// Convert a dependency spec from laygen.toml into a Cargo.toml item.
+pub fn dependency_item(spec: &toml::Value) -> Item {
+    match to_edit_value(spec) {
+        Value::InlineTable(table) => value(table),
+        other => Item::Value(other),
+    }
+}
+
+fn to_edit_value(spec: &toml::Value) -> Value {
+    match spec {
+        toml::Value::String(s) => s.as_str().into(),
+        toml::Value::Integer(i) => (*i).into(),
+        toml::Value::Float(f) => (*f).into(),
+        toml::Value::Boolean(b) => (*b).into(),
+        toml::Value::Datetime(d) => d.to_string().into(),
+        toml::Value::Array(items) => Value::Array(items.iter().map(to_edit_value).collect()),
+        toml::Value::Table(table) => {
+            let mut inline = InlineTable::new();
+            for (key, item) in table {
+                inline.insert(key, to_edit_value(item));
+            }
+            Value::InlineTable(inline)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_dependency_preserves_formatting() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Cargo.toml");
+        fs::write(
+            &path,
+            "[package]\nname = \"api\"  # the api crate\n\n[dependencies]\nsea-orm = { version = \"1\" }\n",
+        )
+        .unwrap();
+
+        let mut manifest = Manifest::load(&path).unwrap();
+        assert!(manifest.has_dependency("sea_orm"));
+        assert!(!manifest.has_dependency("serde"));
+
+        manifest.add_dependency("serde", default_dependency("serde"));
+        manifest.save().unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with("[package]\nname = \"api\"  # the api crate\n"));
+        assert!(content.contains("serde = { version = \"1.0\", features = [\"derive\"] }"));
+    }
+
+    #[test]
+    fn test_find_manifest_parses_the_package_table() {
+        let root = tempfile::tempdir().unwrap();
+        let member = root.path().join("api");
+        fs::create_dir_all(member.join("src")).unwrap();
+        fs::write(root.path().join("Cargo.toml"), "[package] # the app\nname = \"app\"\n").unwrap();
+        // Mentions of `[package]` in comments and strings do not make a package
+        fs::write(member.join("Cargo.toml"), "# [package]\n[workspace]\nexclude = [\"[package]\"]\n").unwrap();
+
+        assert_eq!(find_manifest(&member.join("src")), Some(root.path().join("Cargo.toml")));
+    }
+}
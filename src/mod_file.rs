@@ -0,0 +1,328 @@
+use anyhow::Result;
+use serde::Deserialize;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use walkdir::{DirEntry, WalkDir};
+use crate::plan::GenerationPlan;
+
+/// Files whose `mod` declarations `fmt-mods` sorts.
+const MODULE_FILES: [&str; 3] = ["mod.rs", "lib.rs", "main.rs"];
+
+/// Where new declarations go in a module file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModOrder {
+    /// At the end of the file
+    #[default]
+    Append,
+    /// In alphabetical order within the first block of `mod` declarations
+    Sorted,
+}
+
+/// How a generated module is declared in its parent module file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ModStyle {
+    /// `pub mod name;`
+    #[default]
+    Pub,
+    /// `pub(crate) mod name;`
+    PubCrate,
+    /// `mod name;` plus `pub use name::*;`
+    Glob,
+    /// `mod name;` plus `pub use name::method;` for the generated function
+    Function,
+}
+
+impl ModStyle {
+    /// Whether callers reach the module's items through its parent module.
+    pub fn reexports(self) -> bool {
+        matches!(self, ModStyle::Glob | ModStyle::Function)
+    }
+
+    /// The declaration of `module` and the re-export that goes with it, if any.
+    fn lines(self, module: &str, item: Option<&str>) -> (String, Option<String>) {
+        match (self, item) {
+            (ModStyle::PubCrate, _) => (format!("pub(crate) mod {};", module), None),
+            (ModStyle::Glob, _) => (format!("mod {};", module), Some(format!("pub use {}::*;", module))),
+            (ModStyle::Function, Some(item)) => {
+                (format!("mod {};", module), Some(format!("pub use {}::{};", module, item)))
+            }
+            _ => (format!("pub mod {};", module), None),
+        }
+    }
+}
+
+/// How `mod` declarations are added to module files.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ModPolicy {
+    pub order: ModOrder,
+    pub style: ModStyle,
+}
+
+impl ModPolicy {
+    /// Policy for directory modules, which stay visible so that items re-exported inside them are reachable.
+    pub fn for_directory(self) -> Self {
+        let style = match self.style {
+            ModStyle::PubCrate => ModStyle::PubCrate,
+            _ => ModStyle::Pub,
+        };
+        Self { style, ..self }
+    }
+}
+
+/// A `mod name;` line with the attributes and doc comments above it.
+#[derive(Debug)]
+struct Declaration {
+    lines: Range<usize>,
+    name: String,
+}
+
+/// Module file content with a declaration of `module` added unless it is already declared.
+/// `item` is the generated function, re-exported with [`ModStyle::Function`].
+pub fn declare(content: &str, module: &str, item: Option<&str>, policy: ModPolicy) -> String {
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    if lines.iter().any(|line| declared_name(line) == Some(module)) {
+        return content.to_string();
+    }
+
+    let (declaration, reexport) = policy.style.lines(module, item);
+    insert(&mut lines, declaration, module, declared_name, policy.order);
+    if let Some(reexport) = reexport {
+        insert(&mut lines, reexport, module, reexported_name, policy.order);
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Module file content without the declaration of `module`, the attributes above it and its re-exports.
+pub fn undeclare(content: &str, module: &str) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let removed: Vec<Range<usize>> = [declared_name as fn(&str) -> Option<&str>, reexported_name]
+        .into_iter()
+        .flat_map(|name_of| blocks(&lines, name_of).into_iter().flatten())
+        .filter(|declaration| declaration.name == module)
+        .map(|declaration| declaration.lines)
+        .collect();
+    if removed.is_empty() {
+        return content.to_string();
+    }
+
+    let mut kept: Vec<&str> = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        if removed.iter().any(|range| range.contains(&i)) {
+            continue;
+        }
+        // Don't leave the blank line that separated a removed group behind
+        let blank = line.trim().is_empty();
+        if blank && kept.last().is_none_or(|last| last.trim().is_empty()) {
+            continue;
+        }
+        kept.push(line);
+    }
+    while kept.last().is_some_and(|line| line.trim().is_empty()) {
+        kept.pop();
+    }
+    if kept.is_empty() {
+        return String::new();
+    }
+    kept.join("\n") + "\n"
+}
+
+/// Insert `line` for `name`, sorted into the first block of lines `name_of` recognizes.
+fn insert(lines: &mut Vec<String>, line: String, name: &str, name_of: fn(&str) -> Option<&str>, order: ModOrder) {
+    let refs: Vec<&str> = lines.iter().map(String::as_str).collect();
+    let block = blocks(&refs, name_of).into_iter().next();
+    let position = match (order, block) {
+        (ModOrder::Sorted, Some(block)) => match block.iter().find(|declaration| declaration.name.as_str() > name) {
+            Some(next) => next.lines.start,
+            None => block[block.len() - 1].lines.end,
+        },
+        // Start a separate group, e.g. re-exports below the declarations
+        (ModOrder::Sorted, None) if refs.last().is_some_and(|last| declared_name(last).is_some()) => {
+            lines.push(String::new());
+            lines.len()
+        }
+        _ => lines.len(),
+    };
+    lines.insert(position, line);
+}
+
+/// Sort every block of consecutive `mod` declarations by name, leaving other lines alone.
+pub fn sort_declarations(content: &str) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut out: Vec<&str> = lines.clone();
+    for mut block in blocks(&lines, declared_name) {
+        let start = block[0].lines.start;
+        block.sort_by(|a, b| a.name.cmp(&b.name));
+        let sorted: Vec<&str> = block.iter().flat_map(|declaration| lines[declaration.lines.clone()].to_vec()).collect();
+        out.splice(start..start + sorted.len(), sorted);
+    }
+
+    if content.is_empty() {
+        return String::new();
+    }
+    out.join("\n") + "\n"
+}
+
+/// Re-sort the declarations in every module file below `root`, skipping `target/` and hidden directories.
+pub fn sort_module_files(root: &Path) -> Result<GenerationPlan> {
+    let mut plan = GenerationPlan::default();
+    for path in module_files(root) {
+        let content = fs::read_to_string(&path)?;
+        plan.write(&path, sort_declarations(&content))?;
+    }
+    Ok(plan)
+}
+
+fn module_files(root: &Path) -> Vec<PathBuf> {
+    source_tree(root)
+        .filter(|entry| entry.file_type().is_file() && is_module_file(entry.path()))
+        .map(|entry| entry.into_path())
+        .collect()
+}
+
+/// Entries below `root` in name order, skipping `target/` and hidden directories.
+pub(crate) fn source_tree(root: &Path) -> impl Iterator<Item = DirEntry> {
+    WalkDir::new(root)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            entry.depth() == 0 || !(name.starts_with('.') || (entry.file_type().is_dir() && name == "target"))
+        })
+        .filter_map(|entry| entry.ok())
+}
+
+/// Whether `path` is a mod.rs, lib.rs or main.rs rather than a module of its own.
+pub(crate) fn is_module_file(path: &Path) -> bool {
+    MODULE_FILES.iter().any(|name| path.file_name().is_some_and(|file_name| file_name == *name))
+}
+
+/// Modules declared in `content` with `mod name;`, leaving out inline `mod name { .. }` modules.
+pub(crate) fn declared_modules(content: &str) -> Vec<&str> {
+    content
+        .lines()
+        .filter(|line| line.trim_end().ends_with(';'))
+        .filter_map(declared_name)
+        .collect()
+}
+
+/// Runs of lines named by `name_of`, such as `mod name;` declarations, not separated by blank lines or other items.
+fn blocks(lines: &[&str], name_of: fn(&str) -> Option<&str>) -> Vec<Vec<Declaration>> {
+    let mut blocks = Vec::new();
+    let mut block: Vec<Declaration> = Vec::new();
+    let mut start = 0;
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_start();
+        if let Some(name) = name_of(line).filter(|_| trimmed.ends_with(';')) {
+            block.push(Declaration { lines: start..i + 1, name: name.to_string() });
+            start = i + 1;
+        } else if trimmed.starts_with("#[") || trimmed.starts_with("///") {
+            // Attached to the declaration below, if there is one
+        } else {
+            if !block.is_empty() {
+                blocks.push(std::mem::take(&mut block));
+            }
+            start = i + 1;
+        }
+    }
+    if !block.is_empty() {
+        blocks.push(block);
+    }
+    blocks
+}
+
+/// Name of the module declared on `line`, with any visibility, e.g. `pub(crate) mod user;`.
+fn declared_name(line: &str) -> Option<&str> {
+    let mut rest = line.trim();
+    if let Some(after_pub) = rest.strip_prefix("pub") {
+        rest = after_pub.trim_start();
+        if rest.starts_with('(') {
+            rest = rest[rest.find(')')? + 1..].trim_start();
+        }
+    }
+    let rest = rest.strip_prefix("mod ")?.trim_start();
+    let end = rest.find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '#'))?;
+    let name = &rest[..end];
+    matches!(rest[end..].trim_start().chars().next(), Some(';' | '{')).then_some(name)
+}
+
+/// Module re-exported on `line`, e.g. `user` for `pub use user::create_user;`.
+fn reexported_name(line: &str) -> Option<&str> {
+    let rest = line.trim().strip_prefix("pub use ")?;
+    let (name, _) = rest.split_once("::")?;
+    Some(name.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SORTED: ModPolicy = ModPolicy { order: ModOrder::Sorted, style: ModStyle::Pub };
+
+    #[test]
+    fn test_declare_sorted_within_block() {
+        let content = "//! Admin API\n\npub mod billing;\npub mod user;\n\npub use user::*;\n";
+        assert_eq!(
+            declare(content, "profile", None, SORTED),
+            "//! Admin API\n\npub mod billing;\npub mod profile;\npub mod user;\n\npub use user::*;\n"
+        );
+        assert_eq!(
+            declare(content, "zone", None, SORTED),
+            "//! Admin API\n\npub mod billing;\npub mod user;\npub mod zone;\n\npub use user::*;\n"
+        );
+        assert_eq!(declare(content, "user", None, SORTED), content);
+        assert_eq!(declare("pub mod user_service;\n", "user", None, ModPolicy::default()), "pub mod user_service;\npub mod user;\n");
+        assert_eq!(declare("", "user", None, SORTED), "pub mod user;\n");
+    }
+
+    #[test]
+    fn test_declare_with_reexports() {
+        let glob = ModPolicy { order: ModOrder::Sorted, style: ModStyle::Glob };
+        let content = declare("", "user", None, glob);
+        assert_eq!(content, "mod user;\n\npub use user::*;\n");
+        assert_eq!(declare(&content, "billing", None, glob), "mod billing;\nmod user;\n\npub use billing::*;\npub use user::*;\n");
+
+        let function = ModPolicy { order: ModOrder::Append, style: ModStyle::Function };
+        assert_eq!(
+            declare("mod user;\npub use user::get_user;\n", "create_user", Some("create_user"), function),
+            "mod user;\npub use user::get_user;\nmod create_user;\npub use create_user::create_user;\n"
+        );
+        let crate_only = ModPolicy { order: ModOrder::Append, style: ModStyle::PubCrate };
+        assert_eq!(declare("", "admin", None, crate_only.for_directory()), "pub(crate) mod admin;\n");
+        assert_eq!(declare("", "admin", None, function.for_directory()), "pub mod admin;\n");
+    }
+
+    #[test]
+    fn test_undeclare_removes_attributes_and_reexports() {
+        let content = "pub mod billing;\n/// Users\n#[cfg(feature = \"users\")]\nmod user;\n\npub use user::*;\npub use billing::pay;\n";
+        assert_eq!(undeclare(content, "user"), "pub mod billing;\n\npub use billing::pay;\n");
+        assert_eq!(undeclare(content, "profile"), content);
+        assert_eq!(undeclare("pub mod user;\n", "user"), "");
+    }
+
+    #[test]
+    fn test_sort_module_files_skips_target() {
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir_all(root.path().join("src/admin")).unwrap();
+        fs::create_dir_all(root.path().join("target/debug")).unwrap();
+        fs::write(root.path().join("src/lib.rs"), "pub mod b;\npub mod a;\n").unwrap();
+        fs::write(root.path().join("src/admin/mod.rs"), "pub mod a;\n").unwrap();
+        fs::write(root.path().join("target/debug/mod.rs"), "mod b;\nmod a;\n").unwrap();
+
+        let plan = sort_module_files(root.path()).unwrap();
+        let changed: Vec<_> = plan.changes.iter().map(|change| change.path.clone()).collect();
+        assert_eq!(changed, vec![root.path().join("src/lib.rs")]);
+        assert_eq!(plan.changes[0].content, "pub mod a;\npub mod b;\n");
+    }
+
+    #[test]
+    fn test_sort_declarations_keeps_attributes_and_other_items() {
+        let content = "use crate::x;\n\npub mod user;\n#[cfg(test)]\nmod tests;\npub(crate) mod admin;\n\nmod b;\nmod a;\nfn f() {}\n";
+        assert_eq!(
+            sort_declarations(content),
+            "use crate::x;\n\npub(crate) mod admin;\n#[cfg(test)]\nmod tests;\npub mod user;\n\nmod a;\nmod b;\nfn f() {}\n"
+        );
+    }
+}
@@ -0,0 +1,450 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::generator::{
+    render_dto_module, render_template, to_camel_case, to_pascal_case, to_snake_case, NextLayer, Route,
+    StructField, TemplateExtras,
+};
+use crate::layer::{dto_location, dto_module};
+use crate::plan::{GeneratedModule, GenerationPlan, Generator};
+
+/// Module holding the generated component schemas, placed in the controller root.
+const SCHEMAS_MODULE: &str = "schemas";
+
+const HTTP_METHODS: [&str; 7] = ["get", "put", "post", "delete", "options", "head", "patch"];
+
+const RUST_KEYWORDS: [&str; 38] = [
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+    "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+    "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true",
+    "type", "unsafe", "use", "where", "while",
+];
+
+/// The subset of an OpenAPI 3 document laygen understands.
+#[derive(Debug, Deserialize)]
+pub struct OpenApi {
+    #[serde(default)]
+    pub paths: BTreeMap<String, BTreeMap<String, serde_yaml::Value>>,
+    #[serde(default)]
+    pub components: Components,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Components {
+    #[serde(default)]
+    pub schemas: BTreeMap<String, Schema>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Operation {
+    pub operation_id: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub request_body: Option<Body>,
+    #[serde(default)]
+    pub responses: BTreeMap<String, Body>,
+}
+
+/// A request body or response; only the JSON media type is considered.
+#[derive(Debug, Default, Deserialize)]
+pub struct Body {
+    #[serde(default)]
+    pub content: BTreeMap<String, MediaType>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MediaType {
+    pub schema: Option<Schema>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Schema {
+    #[serde(rename = "$ref")]
+    pub reference: Option<String>,
+    #[serde(rename = "type")]
+    pub schema_type: Option<SchemaType>,
+    pub format: Option<String>,
+    #[serde(default)]
+    pub properties: BTreeMap<String, Schema>,
+    #[serde(default)]
+    pub required: Vec<String>,
+    pub items: Option<Box<Schema>>,
+    #[serde(rename = "enum", default)]
+    pub enum_values: Vec<serde_yaml::Value>,
+    #[serde(default)]
+    pub nullable: bool,
+}
+
+/// OpenAPI 3.0 uses a single type name, 3.1 allows a list such as `[string, "null"]`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum SchemaType {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+impl Schema {
+    fn type_name(&self) -> Option<&str> {
+        match &self.schema_type {
+            Some(SchemaType::Single(t)) => Some(t.as_str()),
+            Some(SchemaType::Multiple(types)) => {
+                types.iter().map(String::as_str).find(|t| *t != "null")
+            }
+            None if !self.properties.is_empty() => Some("object"),
+            None => None,
+        }
+    }
+
+    fn is_nullable(&self) -> bool {
+        self.nullable
+            || matches!(&self.schema_type, Some(SchemaType::Multiple(types)) if types.iter().any(|t| t == "null"))
+    }
+
+    fn ref_name(&self) -> Option<&str> {
+        self.reference.as_deref().and_then(|r| r.rsplit('/').next())
+    }
+}
+
+/// One operation mapped onto a controller/service pair.
+#[derive(Debug)]
+pub struct Endpoint {
+    /// Subdirectory (from the first tag) below the controller and service directories
+    pub subdir: Option<String>,
+    pub name: String,
+    pub route: Route,
+    pub request: Option<Schema>,
+    pub response: Option<Schema>,
+}
+
+impl OpenApi {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        // YAML is a superset of JSON, so this handles both formats
+        serde_yaml::from_str(&content)
+            .with_context(|| format!("failed to parse OpenAPI document {}", path.display()))
+    }
+
+    pub fn endpoints(&self) -> Result<Vec<Endpoint>> {
+        let mut endpoints = Vec::new();
+
+        for (path, item) in &self.paths {
+            for (method, value) in item {
+                if !HTTP_METHODS.contains(&method.as_str()) {
+                    continue;
+                }
+                let operation: Operation = serde_yaml::from_value(value.clone())
+                    .with_context(|| format!("invalid operation {} {}", method, path))?;
+
+                let name = match &operation.operation_id {
+                    Some(id) => to_snake_case(id),
+                    None => to_snake_case(&format!("{} {}", method, path)),
+                };
+                let tag = operation.tags.first().cloned();
+
+                endpoints.push(Endpoint {
+                    subdir: tag.as_deref().map(to_snake_case).filter(|t| !t.is_empty()),
+                    name,
+                    route: Route {
+                        method: method.clone(),
+                        path: path.clone(),
+                        tag,
+                    },
+                    request: operation.request_body.as_ref().and_then(json_schema),
+                    response: operation
+                        .responses
+                        .iter()
+                        .find(|(status, _)| status.starts_with('2'))
+                        .and_then(|(_, body)| json_schema(body)),
+                });
+            }
+        }
+
+        Ok(endpoints)
+    }
+
+    /// Resolve a `$ref` to the referenced component, or return the schema itself.
+    fn resolve<'a>(&'a self, schema: &'a Schema) -> &'a Schema {
+        schema
+            .ref_name()
+            .and_then(|name| self.components.schemas.get(name))
+            .unwrap_or(schema)
+    }
+
+    /// Fields for a request/response struct built from an operation's schema.
+    fn fields_of(&self, schema: Option<&Schema>) -> Vec<StructField> {
+        match schema {
+            Some(schema) => struct_fields(self.resolve(schema)),
+            None => Vec::new(),
+        }
+    }
+
+    /// Render all component schemas as Rust types deriving `ToSchema`.
+    pub fn render_schemas(&self) -> String {
+        let mut out = String::from(
+            "use serde::{Deserialize, Serialize};\nuse utoipa::ToSchema;\n",
+        );
+
+        for (name, schema) in &self.components.schemas {
+            out.push('\n');
+            out.push_str(&render_schema(&to_pascal_case(name), schema));
+        }
+
+        out
+    }
+}
+
+fn json_schema(body: &Body) -> Option<Schema> {
+    body.content
+        .iter()
+        .find(|(media, _)| media.contains("json"))
+        .or_else(|| body.content.iter().next())
+        .and_then(|(_, media)| media.schema.clone())
+}
+
+fn struct_fields(schema: &Schema) -> Vec<StructField> {
+    schema
+        .properties
+        .iter()
+        .map(|(name, property)| {
+            let mut ty = rust_type(property);
+            if !schema.required.contains(name) || property.is_nullable() {
+                ty = format!("Option<{}>", ty);
+            }
+            field(name, ty)
+        })
+        .collect()
+}
+
+fn field(wire_name: &str, ty: String) -> StructField {
+    let snake = to_snake_case(wire_name);
+    // `rename_all = "camelCase"` maps snake_case fields back to camelCase wire names
+    let rename = (to_camel_case(&snake) != wire_name).then(|| wire_name.to_string());
+    let name = if RUST_KEYWORDS.contains(&snake.as_str()) {
+        format!("r#{}", snake)
+    } else {
+        snake
+    };
+
+    StructField { name, ty, rename }
+}
+
+/// Map a schema onto the Rust type used for a field.
+fn rust_type(schema: &Schema) -> String {
+    if let Some(name) = schema.ref_name() {
+        return to_pascal_case(name);
+    }
+
+    match (schema.type_name(), schema.format.as_deref()) {
+        (Some("string"), _) => "String".to_string(),
+        (Some("integer"), Some("int64")) => "i64".to_string(),
+        (Some("integer"), _) => "i32".to_string(),
+        (Some("number"), Some("float")) => "f32".to_string(),
+        (Some("number"), _) => "f64".to_string(),
+        (Some("boolean"), _) => "bool".to_string(),
+        (Some("array"), _) => match &schema.items {
+            Some(items) => format!("Vec<{}>", rust_type(items)),
+            None => "Vec<serde_json::Value>".to_string(),
+        },
+        _ => "serde_json::Value".to_string(),
+    }
+}
+
+fn render_schema(name: &str, schema: &Schema) -> String {
+    let string_enum: Vec<&str> = schema.enum_values.iter().filter_map(|v| v.as_str()).collect();
+
+    if schema.type_name() == Some("object") || !schema.properties.is_empty() {
+        let fields = crate::generator::render_fields(&struct_fields(schema), "");
+        format!(
+            "#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]\n#[serde(rename_all = \"camelCase\")]\npub struct {} {{\n{}\n}}\n",
+            name,
+            fields.trim_end()
+        )
+    } else if !string_enum.is_empty() {
+        let variants = string_enum
+            .iter()
+            .map(|value| format!("    #[serde(rename = \"{}\")]\n    {},", value, to_pascal_case(&to_snake_case(value))))
+            .collect::<Vec<_>>()
+            .join("\n");
+        format!(
+            "#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]\npub enum {} {{\n{}\n}}\n",
+            name, variants
+        )
+    } else {
+        format!("pub type {} = {};\n", name, rust_type(schema))
+    }
+}
+
+/// Plan a file in every layer for each operation in the document.
+///
+/// Request/response structs and the route attribute go into the outermost layer
+/// (or into the generator's DTO directory when set), component schemas into a
+/// `schemas` module in the root directory of whichever holds the structs.
+/// Files that already exist are skipped.
+pub fn import_openapi(spec_path: &Path, generator: &Generator) -> Result<GenerationPlan> {
+    let api = OpenApi::from_file(spec_path)?;
+    let endpoints = api.endpoints()?;
+    let mut plan = GenerationPlan::default();
+    let layers = &generator.layers;
+    if layers.is_empty() {
+        return Ok(plan);
+    }
+
+    let target = &generator.target;
+    let locations = generator.resolve_locations()?;
+    let policy = generator.mod_policy();
+    let dto_dir = generator.dto_dir.as_deref();
+    let dto_location = dto_dir.map(|dir| dto_location(dir, target, &generator.layers, &locations)).transpose()?;
+    let structs_root = match &dto_location {
+        Some(dto_location) => dto_location.path.clone(),
+        None => locations[0].path.clone(),
+    };
+
+    if !api.components.schemas.is_empty() {
+        let schemas_file = structs_root.join(format!("{}.rs", SCHEMAS_MODULE));
+        generator.write_formatted(&mut plan, &schemas_file, api.render_schemas())?;
+        plan.declare_module(&structs_root, SCHEMAS_MODULE, None, policy)?;
+    }
+
+    for endpoint in &endpoints {
+        let subdir = PathBuf::from(endpoint.subdir.clone().unwrap_or_default());
+        // Wire new tag directories into the layer roots
+        for (layer, location) in layers.iter().zip(&locations) {
+            plan.ensure_module_dir(&location.path, &subdir, layer.mod_policy(generator.mod_order))?;
+        }
+
+        let request_fields = api.fields_of(endpoint.request.as_ref());
+        let response_fields = api.fields_of(endpoint.response.as_ref());
+        let mut imports = Vec::new();
+        let uses_components = request_fields
+            .iter()
+            .chain(&response_fields)
+            .flat_map(|f| f.ty.split(|c: char| !c.is_alphanumeric() && c != '_'))
+            .any(|ty| api.components.schemas.keys().any(|name| to_pascal_case(name) == ty));
+        if uses_components {
+            let depth = subdir.iter().count();
+            imports.push(format!("{}{}::*", "super::".repeat(depth + 1), SCHEMAS_MODULE));
+        }
+        let mut outer_extras = TemplateExtras {
+            imports,
+            request_fields,
+            response_fields,
+            route: Some(endpoint.route.clone()),
+            dto: None,
+            comment_language: generator.comment_language,
+        };
+
+        let file_names: Vec<String> = layers
+            .iter()
+            .map(|layer| format!("{}{}", endpoint.name, layer.suffix))
+            .collect();
+
+        if let (Some(dto_dir), Some(dto_location)) = (dto_dir, &dto_location) {
+            let dto_path = generator.ensure_dto_dir(&mut plan, dto_location, &locations, &subdir)?;
+            let dto_file = dto_path.join(format!("{}.rs", file_names[0]));
+            if dto_file.exists() {
+                plan.skipped.push(dto_file);
+            } else {
+                generator.write_formatted(&mut plan, &dto_file, render_dto_module(&file_names[0], &outer_extras))?;
+                plan.declare_module(&dto_path, &file_names[0], None, policy)?;
+            }
+            outer_extras.dto = Some(dto_module(dto_dir, dto_location, &generator.layers, &locations, &subdir, &file_names[0]));
+        }
+
+        let new_handler = !locations[0].path.join(&subdir).join(format!("{}.rs", file_names[0])).exists();
+        for (i, layer) in layers.iter().enumerate() {
+            let dir_path = locations[i].path.join(&subdir);
+            let file_path = dir_path.join(format!("{}.rs", file_names[i]));
+            if file_path.exists() {
+                plan.skipped.push(file_path);
+                continue;
+            }
+
+            let next = layers.get(i + 1).map(|next_layer| NextLayer {
+                template: next_layer.template(target),
+                import: next_layer.caller_import(&locations[i + 1], &locations[i], &subdir, &file_names[i + 1]),
+                file_name: file_names[i + 1].clone(),
+                method_name: endpoint.name.clone(),
+            });
+            let extras = outer_extras.for_layer(i);
+
+            let content = render_template(&layer.template(target), &file_names[i], &endpoint.name, next.as_ref(), &extras)?;
+            generator.write_formatted(&mut plan, &file_path, content)?;
+            plan.declare_module(&dir_path, &file_names[i], Some(&endpoint.name), layer.mod_policy(generator.mod_order))?;
+            plan.modules.push(GeneratedModule {
+                layer: layer.name.clone(),
+                module: layer.module_import_at(&locations[i], &locations[i], &subdir, &file_names[i]),
+                path: file_path,
+            });
+        }
+        // Only new handlers get a test; existing ones may already have one
+        if new_handler {
+            generator.plan_test(&mut plan, &locations, &subdir, &file_names[0], &endpoint.name, &outer_extras)?;
+        }
+        plan.routes.push(endpoint.route.clone());
+    }
+
+    plan.dependencies = generator.dependencies(&locations, dto_location.as_ref())?;
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPEC: &str = r#"
+openapi: 3.0.3
+paths:
+  /admin/users:
+    post:
+      tags: [Admin User]
+      operationId: createUser
+      requestBody:
+        content:
+          application/json:
+            schema:
+              $ref: '#/components/schemas/NewUser'
+      responses:
+        '200':
+          description: ok
+components:
+  schemas:
+    NewUser:
+      type: object
+      required: [userName]
+      properties:
+        userName: {type: string}
+        created_at: {type: string}
+        type: {type: integer, format: int64}
+"#;
+
+    #[test]
+    fn test_endpoints_and_fields() {
+        let api: OpenApi = serde_yaml::from_str(SPEC).unwrap();
+        let endpoints = api.endpoints().unwrap();
+        assert_eq!(endpoints.len(), 1);
+
+        let endpoint = &endpoints[0];
+        assert_eq!(endpoint.name, "create_user");
+        assert_eq!(endpoint.subdir.as_deref(), Some("admin_user"));
+        assert_eq!(endpoint.route.method, "post");
+
+        let fields = api.fields_of(endpoint.request.as_ref());
+        let rendered: Vec<_> = fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.ty.as_str(), f.rename.as_deref()))
+            .collect();
+        assert_eq!(
+            rendered,
+            vec![
+                ("created_at", "Option<String>", Some("created_at")),
+                ("r#type", "Option<i64>", None),
+                ("user_name", "String", None),
+            ]
+        );
+    }
+}
@@ -0,0 +1,737 @@
+use anyhow::{bail, Result};
+use serde::Serialize;
+use similar::TextDiff;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml_edit::Item;
+use crate::config::ProjectConfig;
+use crate::file_ops::mod_file_path;
+use crate::format::{trim_trailing_whitespace, Formatter};
+use crate::generator::{
+    render_dto_module, render_template, render_test, render_test_support, Route, Template, TemplateExtras,
+    TEST_DEPENDENCIES,
+};
+use crate::i18n::Language;
+use crate::layer::{dto_location, dto_module, next_layer, Layer, LayerLocation, LayerSelection};
+use crate::mod_file::{declare, ModOrder, ModPolicy, ModStyle};
+use crate::manifest::{
+    find_manifest, find_workspace_manifest, path_dependency, workspace_dependency, Manifest,
+};
+use crate::workspace::Workspace;
+
+/// Generates layered files for a target project without prompting or printing.
+///
+/// ```no_run
+/// use laygen::plan::{GenerationRequest, Generator};
+///
+/// let plan = Generator::new("my-app")?
+///     .plan(&GenerationRequest::new().layer("admin", "user", "create_user").layer("admin", "user_service", ""))?;
+/// plan.apply()?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct Generator {
+    pub(crate) target: PathBuf,
+    pub(crate) config: ProjectConfig,
+    pub(crate) layers: Vec<Layer>,
+    pub(crate) locations: Option<Vec<LayerLocation>>,
+    pub(crate) dto_dir: Option<String>,
+    pub(crate) tests_dir: Option<String>,
+    pub(crate) comment_language: Language,
+    pub(crate) formatter: Formatter,
+    pub(crate) mod_order: ModOrder,
+}
+
+impl Generator {
+    /// Generator for `target`, with the layers and DTO directory from its `laygen.toml`.
+    pub fn new(target: impl Into<PathBuf>) -> Result<Self> {
+        let target = target.into();
+        let config = ProjectConfig::load(&target)?;
+        Ok(Self {
+            layers: config.layers("controller", "service"),
+            dto_dir: config.dto_dir.clone(),
+            tests_dir: config.tests_dir.clone(),
+            formatter: config.format,
+            mod_order: config.mod_order,
+            target,
+            config,
+            locations: None,
+            comment_language: Language::default(),
+        })
+    }
+
+    /// Layers to generate, outermost first.
+    pub fn layers(mut self, layers: Vec<Layer>) -> Self {
+        self.layers = layers;
+        self
+    }
+
+    /// Where each layer lives. Defaults to `<target>/<dir>`, or in a workspace to the
+    /// member configured for the layer or named after it.
+    pub fn locations(mut self, locations: Vec<LayerLocation>) -> Self {
+        self.locations = Some(locations);
+        self
+    }
+
+    /// Emit request/response DTOs into this directory instead of the controller file.
+    pub fn dto_dir(mut self, dto_dir: impl Into<String>) -> Self {
+        self.dto_dir = Some(dto_dir.into());
+        self
+    }
+
+    /// Also generate an integration test for each new handler in this directory below the
+    /// crate root, e.g. `tests/api`.
+    pub fn tests_dir(mut self, tests_dir: impl Into<String>) -> Self {
+        self.tests_dir = Some(tests_dir.into());
+        self
+    }
+
+    /// Language of the placeholder comments in generated code.
+    pub fn comment_language(mut self, language: Language) -> Self {
+        self.comment_language = language;
+        self
+    }
+
+    /// Formatter for generated files, overriding the project's `format` setting.
+    pub fn formatter(mut self, formatter: Formatter) -> Self {
+        self.formatter = formatter;
+        self
+    }
+
+    /// Where declarations of new modules go in existing module files.
+    pub fn mod_order(mut self, order: ModOrder) -> Self {
+        self.mod_order = order;
+        self
+    }
+
+    /// File changes for one file per layer, each wired to the file in the next layer.
+    pub fn plan(&self, request: &GenerationRequest) -> Result<GenerationPlan> {
+        if request.selections.len() != self.layers.len() {
+            bail!(
+                "expected a file for each of the {} layers, got {}",
+                self.layers.len(),
+                request.selections.len()
+            );
+        }
+        let locations = self.resolve_locations()?;
+        let policy = self.mod_policy();
+        let mut plan = GenerationPlan::default();
+
+        // Emit DTOs mirroring the outermost subdirectory, shared by the first two layers
+        let mut outer_extras = TemplateExtras {
+            route: request.route.clone(),
+            comment_language: self.comment_language,
+            ..TemplateExtras::default()
+        };
+        let dto_location = self
+            .dto_dir
+            .as_deref()
+            .map(|dir| dto_location(dir, &self.target, &self.layers, &locations))
+            .transpose()?;
+        if let (Some(dto_dir), Some(dto_location)) = (self.dto_dir.as_deref(), &dto_location) {
+            let outer = &request.selections[0];
+            let dto_path = self.ensure_dto_dir(&mut plan, dto_location, &locations, &outer.subdir)?;
+            let dto_file = dto_path.join(format!("{}.rs", outer.file_name));
+            self.write_formatted(&mut plan, &dto_file, render_dto_module(&outer.file_name, &outer_extras))?;
+            plan.declare_module(&dto_path, &outer.file_name, None, policy)?;
+            outer_extras.dto = Some(dto_module(dto_dir, dto_location, &self.layers, &locations, &outer.subdir, &outer.file_name));
+        }
+
+        // Generate from the outermost layer down, each file wired to the next layer
+        for (i, (layer, selection)) in self.layers.iter().zip(&request.selections).enumerate() {
+            let next = next_layer(&self.target, &self.layers, &locations, &request.selections, i);
+            let policy = layer.mod_policy(self.mod_order);
+            let dir = plan.ensure_module_dir(&locations[i].path, &selection.subdir, policy)?;
+            let content = render_template(
+                &layer.template(&self.target),
+                &selection.file_name,
+                &selection.method_name,
+                next.as_ref(),
+                &outer_extras.for_layer(i),
+            )?;
+            let path = dir.join(format!("{}.rs", selection.file_name));
+            self.write_formatted(&mut plan, &path, content)?;
+            plan.declare_module(&dir, &selection.file_name, Some(&selection.method_name), policy)?;
+            plan.modules.push(GeneratedModule {
+                layer: layer.name.clone(),
+                module: layer.module_import_at(&locations[i], &locations[i], &selection.subdir, &selection.file_name),
+                path,
+            });
+        }
+        let outer = &request.selections[0];
+        self.plan_test(&mut plan, &locations, &outer.subdir, &outer.file_name, &outer.method_name, &outer_extras)?;
+        plan.routes.extend(request.route.clone());
+
+        plan.dependencies = self.dependencies(&locations, dto_location.as_ref())?;
+        Ok(plan)
+    }
+
+    /// Plan an integration test for the handler `method_name` in `subdir/file_name` of the
+    /// outermost layer, wired into the test crate in `tests_dir` next to a shared support module.
+    pub(crate) fn plan_test(
+        &self,
+        plan: &mut GenerationPlan,
+        locations: &[LayerLocation],
+        subdir: &Path,
+        file_name: &str,
+        method_name: &str,
+        extras: &TemplateExtras,
+    ) -> Result<()> {
+        let (Some(tests_dir), Some(layer), Some(location)) = (&self.tests_dir, self.layers.first(), locations.first()) else {
+            return Ok(());
+        };
+        let Some(manifest) = find_manifest(&location.path) else {
+            bail!("Cargo.toml not found for {}", location.path.display());
+        };
+        // Integration tests reach the handler and its request type through the library crate
+        let package = Manifest::load(&manifest)?;
+        if !package.has_lib() {
+            bail!("integration tests need a library target, but {} has no [lib] or src/lib.rs", manifest.display());
+        }
+        match layer.mod_style {
+            ModStyle::PubCrate => bail!(
+                "integration tests cannot reach the {} layer, whose modules are declared pub(crate)",
+                layer.name
+            ),
+            ModStyle::Function if extras.dto.is_none() => bail!(
+                "integration tests cannot reach the request type of the {} layer, which only re-exports the handler",
+                layer.name
+            ),
+            _ => {}
+        }
+        let krate = match &location.member {
+            Some(member) => member.name.clone(),
+            None => match package.package_name() {
+                Some(name) => name.to_string(),
+                None => bail!("no package name in {}", manifest.display()),
+            },
+        }
+        .replace('-', "_");
+
+        let crate_root = manifest.parent().unwrap_or(Path::new("."));
+        let root = crate_root.join(tests_dir);
+        let policy = self.mod_policy();
+
+        // `tests/<name>/main.rs` is a test target of its own, with the shared support module
+        let main = root.join("main.rs");
+        if !plan.exists(&main) && !plan.exists(&root.join("mod.rs")) {
+            plan.write(&main, String::new())?;
+        }
+        let support = root.join("support.rs");
+        if !plan.exists(&support) {
+            self.write_formatted(plan, &support, render_test_support(self.comment_language))?;
+            plan.declare_module(&root, "support", None, policy)?;
+        }
+
+        let dir = plan.ensure_module_dir(&root, subdir, policy)?;
+        let path = dir.join(format!("{}.rs", file_name));
+        if plan.exists(&path) {
+            plan.skipped.push(path);
+            return Ok(());
+        }
+        // A re-exporting layer exposes the handler through the parent module
+        let module = layer.caller_import(location, location, subdir, file_name);
+        let content = render_test(&module, &krate, subdir, file_name, method_name, extras);
+        self.write_formatted(plan, &path, content)?;
+        plan.declare_module(&dir, file_name, None, policy)
+    }
+
+    /// Plan the DTO directory mirroring `subdir`. Inside the directory of the layer owning
+    /// the DTOs, the DTO root itself is declared there as part of that crate.
+    pub(crate) fn ensure_dto_dir(
+        &self,
+        plan: &mut GenerationPlan,
+        dto_location: &LayerLocation,
+        locations: &[LayerLocation],
+        subdir: &Path,
+    ) -> Result<PathBuf> {
+        let owner = locations.get(1).unwrap_or(&locations[0]);
+        match dto_location.path.strip_prefix(&owner.path) {
+            Ok(dto_dir) if dto_location.member.is_none() => {
+                plan.ensure_module_dir(&owner.path, &dto_dir.join(subdir), self.mod_policy())
+            }
+            _ => plan.ensure_module_dir(&dto_location.path, subdir, self.mod_policy()),
+        }
+    }
+
+    /// Policy for modules that are not part of a layer, such as DTOs and schemas.
+    pub(crate) fn mod_policy(&self) -> ModPolicy {
+        ModPolicy { order: self.mod_order, ..ModPolicy::default() }
+    }
+
+    /// Plan `source` as the content of `path`, formatted. When rustfmt rejects it under `Auto`,
+    /// it is written with only trailing whitespace trimmed and the plan records why.
+    pub(crate) fn write_formatted(&self, plan: &mut GenerationPlan, path: &Path, source: String) -> Result<()> {
+        let content = match self.formatter.format(&source, path) {
+            Ok(content) => content,
+            Err(err) if self.formatter == Formatter::Auto => {
+                plan.unformatted.push(Unformatted { path: path.to_path_buf(), error: format!("{:#}", err) });
+                trim_trailing_whitespace(&source)
+            }
+            Err(err) => return Err(err),
+        };
+        plan.write(path, content)
+    }
+
+    pub(crate) fn resolve_locations(&self) -> Result<Vec<LayerLocation>> {
+        self.resolve_locations_with(|_, _, default| Ok(default))
+    }
+
+    /// Where each layer lives. In a cargo workspace, `choose` picks the member for each layer
+    /// without a configured crate, given the member names and the index of the one named after the layer.
+    pub(crate) fn resolve_locations_with(
+        &self,
+        mut choose: impl FnMut(&Layer, &[String], usize) -> Result<usize>,
+    ) -> Result<Vec<LayerLocation>> {
+        if let Some(locations) = &self.locations {
+            return Ok(locations.clone());
+        }
+        let Some(workspace) = Workspace::load(&self.target)? else {
+            return Ok(self
+                .layers
+                .iter()
+                .map(|layer| LayerLocation { path: self.target.join(&layer.dir), member: None })
+                .collect());
+        };
+
+        let names: Vec<String> = workspace.members.iter().map(|member| member.name.clone()).collect();
+        let mut locations = Vec::with_capacity(self.layers.len());
+        for layer in &self.layers {
+            let member = match &layer.krate {
+                Some(name) => match workspace.member(name) {
+                    Some(member) => member,
+                    None => bail!("workspace member not found: {}", name),
+                },
+                None => {
+                    // Default to a member named after the layer, e.g. `service-core` for `service`
+                    let default = names.iter().position(|name| name.contains(&layer.name)).unwrap_or(0);
+                    &workspace.members[choose(layer, &names, default)?]
+                }
+            };
+            locations.push(LayerLocation {
+                path: member.src_dir.join(&layer.dir),
+                member: Some(member.clone()),
+            });
+        }
+        Ok(locations)
+    }
+
+    /// Dependencies the generated files need that the Cargo.toml owning each layer lacks:
+    /// template imports plus path dependencies between crates.
+    pub(crate) fn dependencies(
+        &self,
+        locations: &[LayerLocation],
+        dto: Option<&LayerLocation>,
+    ) -> Result<Vec<DependencyChange>> {
+        let mut required: BTreeMap<PathBuf, Vec<(String, Item)>> = BTreeMap::new();
+        let mut require = |location: &LayerLocation, name: &str, item: Item| {
+            if let Some(manifest) = find_manifest(&location.path) {
+                let deps = required.entry(manifest).or_default();
+                if !deps.iter().any(|(existing, _)| existing == name) {
+                    deps.push((name.to_string(), item));
+                }
+            }
+        };
+
+        for (i, (layer, location)) in self.layers.iter().zip(locations).enumerate() {
+            for name in layer.template(&self.target).dependencies() {
+                require(location, name, self.config.dependency(name));
+            }
+            let next_member = locations.get(i + 1).and_then(|next| next.member.as_ref());
+            if let (Some(from), Some(to)) = (&location.member, next_member) {
+                if from != to {
+                    require(location, &to.name, path_dependency(&to.dependency_path(from)));
+                }
+            }
+        }
+        if let Some(dto) = dto {
+            for name in Template::Dto.dependencies() {
+                require(dto, name, self.config.dependency(name));
+            }
+        }
+        // Generated tests build on the crate of the outermost layer
+        let tests_manifest = self.tests_dir.as_ref().and_then(|_| find_manifest(&locations.first()?.path));
+        if let Some(manifest) = &tests_manifest {
+            required.entry(manifest.clone()).or_default();
+        }
+
+        let mut changes = Vec::new();
+        for (manifest_path, deps) in required {
+            let manifest = Manifest::load(&manifest_path)?;
+            let workspace = find_workspace_manifest(&manifest_path)
+                .map(|root| Manifest::load(&root))
+                .transpose()?;
+            // Prefer versions pinned in `[workspace.dependencies]`
+            let pin = |(name, item): (String, Item)| match &workspace {
+                Some(root) if root.has_workspace_dependency(&name) => (name, workspace_dependency()),
+                _ => (name, item),
+            };
+            let dependencies: Vec<(String, Item)> = deps
+                .into_iter()
+                .filter(|(name, _)| !manifest.has_dependency(name))
+                .map(pin)
+                .collect();
+            let dev_dependencies: Vec<(String, Item)> = match &tests_manifest {
+                Some(tests_manifest) if *tests_manifest == manifest_path => TEST_DEPENDENCIES
+                    .iter()
+                    .filter(|name| !manifest.has_dev_dependency(name))
+                    .map(|name| pin((name.to_string(), self.config.dependency(name))))
+                    .collect(),
+                _ => Vec::new(),
+            };
+            if !dependencies.is_empty() || !dev_dependencies.is_empty() {
+                changes.push(DependencyChange { manifest: manifest_path, dependencies, dev_dependencies });
+            }
+        }
+        Ok(changes)
+    }
+}
+
+/// The file to generate in each layer, outermost first, and the route it serves.
+#[derive(Debug, Clone, Default)]
+pub struct GenerationRequest {
+    pub selections: Vec<LayerSelection>,
+    pub route: Option<Route>,
+}
+
+impl GenerationRequest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the file for the next layer down. An empty method name defaults to the file name.
+    pub fn layer(mut self, subdir: impl Into<PathBuf>, file_name: &str, method_name: &str) -> Self {
+        let method_name = if method_name.is_empty() { file_name } else { method_name };
+        self.selections.push(LayerSelection {
+            subdir: subdir.into(),
+            file_name: file_name.to_string(),
+            method_name: method_name.to_string(),
+        });
+        self
+    }
+
+    /// Route served by the outermost layer, rendered as a `#[utoipa::path]` attribute.
+    pub fn route(mut self, route: Route) -> Self {
+        self.route = Some(route);
+        self
+    }
+}
+
+/// Unified diff between two versions of a file, which may live at different paths.
+pub fn unified_diff(old_path: &str, old: &str, new_path: &str, new: &str) -> String {
+    TextDiff::from_lines(old, new).unified_diff().header(old_path, new_path).to_string()
+}
+
+/// Everything a generation would change, computed before anything is written.
+#[derive(Debug, Default)]
+pub struct GenerationPlan {
+    /// Files to create or overwrite, in the order they were first touched
+    pub changes: Vec<FileChange>,
+    /// The module generated in each layer
+    pub modules: Vec<GeneratedModule>,
+    pub routes: Vec<Route>,
+    /// Files left alone because they already exist
+    pub skipped: Vec<PathBuf>,
+    /// Dependencies to add, which `apply` leaves to the caller
+    pub dependencies: Vec<DependencyChange>,
+    /// Files to delete once the changes are written
+    pub removed: Vec<PathBuf>,
+    /// Files planned unformatted because rustfmt rejected them
+    pub unformatted: Vec<Unformatted>,
+}
+
+/// New content for one file.
+#[derive(Debug, Clone)]
+pub struct FileChange {
+    pub path: PathBuf,
+    /// Content on disk, or `None` when the file is created
+    pub before: Option<String>,
+    pub content: String,
+}
+
+/// A generated file rustfmt rejected, so it is written as rendered.
+#[derive(Debug, Clone, Serialize)]
+pub struct Unformatted {
+    pub path: PathBuf,
+    /// What rustfmt reported
+    pub error: String,
+}
+
+/// A module generated for one layer.
+#[derive(Debug, Clone, Serialize)]
+pub struct GeneratedModule {
+    pub layer: String,
+    pub path: PathBuf,
+    /// `use` path of the module within its crate
+    pub module: String,
+}
+
+/// Dependencies missing from one Cargo.toml.
+#[derive(Debug, Clone)]
+pub struct DependencyChange {
+    pub manifest: PathBuf,
+    pub dependencies: Vec<(String, Item)>,
+    /// Dependencies only the generated tests need
+    pub dev_dependencies: Vec<(String, Item)>,
+}
+
+impl DependencyChange {
+    pub fn names(&self) -> Vec<&str> {
+        self.dependencies.iter().chain(&self.dev_dependencies).map(|(name, _)| name.as_str()).collect()
+    }
+
+    /// Add the dependencies to the manifest.
+    pub fn apply(&self) -> Result<()> {
+        let mut manifest = Manifest::load(&self.manifest)?;
+        for (name, item) in &self.dependencies {
+            manifest.add_dependency(name, item.clone());
+        }
+        for (name, item) in &self.dev_dependencies {
+            manifest.add_dev_dependency(name, item.clone());
+        }
+        manifest.save()
+    }
+}
+
+impl FileChange {
+    pub fn is_new(&self) -> bool {
+        self.before.is_none()
+    }
+
+    /// Unified diff from the content on disk to the planned content.
+    pub fn diff(&self) -> String {
+        let path = self.path.display().to_string();
+        let old_path = if self.is_new() { "/dev/null" } else { path.as_str() };
+        unified_diff(old_path, self.before.as_deref().unwrap_or_default(), &path, &self.content)
+    }
+}
+
+impl GenerationPlan {
+    /// Write every file change, creating directories as needed.
+    pub fn apply(&self) -> Result<()> {
+        for change in &self.changes {
+            if let Some(dir) = change.path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(&change.path, &change.content)?;
+        }
+        for path in &self.removed {
+            if path.is_file() {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Content of `path` as it will be after the changes planned so far.
+    pub(crate) fn read(&self, path: &Path) -> Result<Option<String>> {
+        if self.removed.iter().any(|removed| removed == path) {
+            return Ok(None);
+        }
+        match self.changes.iter().find(|change| change.path == path) {
+            Some(change) => Ok(Some(change.content.clone())),
+            None if path.is_file() => Ok(Some(fs::read_to_string(path)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub(crate) fn exists(&self, path: &Path) -> bool {
+        (path.exists() && !self.removed.iter().any(|removed| removed == path))
+            || self.changes.iter().any(|change| change.path.starts_with(path))
+    }
+
+    /// Delete `path`, dropping any change planned for it.
+    pub(crate) fn remove(&mut self, path: &Path) {
+        self.changes.retain(|change| change.path != path);
+        if path.is_file() && !self.removed.iter().any(|removed| removed == path) {
+            self.removed.push(path.to_path_buf());
+        }
+    }
+
+    pub(crate) fn write(&mut self, path: &Path, content: String) -> Result<()> {
+        self.removed.retain(|removed| removed != path);
+        if let Some(change) = self.changes.iter_mut().find(|change| change.path == path) {
+            change.content = content;
+            return Ok(());
+        }
+        let before = if path.is_file() { Some(fs::read_to_string(path)?) } else { None };
+        if before.as_deref() != Some(content.as_str()) {
+            self.changes.push(FileChange { path: path.to_path_buf(), before, content });
+        }
+        Ok(())
+    }
+
+    /// Declare `module` in the mod.rs or lib.rs of `dir`.
+    /// `item` is the generated function, for layers that re-export it.
+    pub(crate) fn declare_module(&mut self, dir: &Path, module: &str, item: Option<&str>, policy: ModPolicy) -> Result<()> {
+        let mod_file = mod_file_path(dir, |path| self.exists(path));
+        let content = self.read(&mod_file)?.unwrap_or_default();
+        self.write(&mod_file, declare(&content, module, item, policy))
+    }
+
+    /// Plan the directories of `subdir` below `root`, each with a mod.rs declared in its parent.
+    pub(crate) fn ensure_module_dir(&mut self, root: &Path, subdir: &Path, policy: ModPolicy) -> Result<PathBuf> {
+        let mut current = root.to_path_buf();
+        for component in subdir.iter() {
+            let next = current.join(component);
+            if !self.exists(&next) {
+                self.write(&next.join("mod.rs"), String::new())?;
+                self.declare_module(&current, &component.to_string_lossy(), None, policy.for_directory())?;
+            }
+            current = next;
+        }
+        Ok(current)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_writes_nothing_until_applied() {
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir_all(root.path().join("controller")).unwrap();
+        fs::write(root.path().join("controller/mod.rs"), "pub mod health;\n").unwrap();
+
+        let plan = Generator::new(root.path())
+            .unwrap()
+            .plan(&GenerationRequest::new().layer("admin", "user", "create_user").layer("admin", "user_service", ""))
+            .unwrap();
+        assert!(!root.path().join("controller/admin").exists());
+
+        let paths: Vec<_> = plan
+            .changes
+            .iter()
+            .map(|change| (change.path.strip_prefix(root.path()).unwrap().to_str().unwrap(), change.is_new()))
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                ("controller/admin/mod.rs", true),
+                ("controller/mod.rs", false),
+                ("controller/admin/user.rs", true),
+                ("service/admin/mod.rs", true),
+                ("service/mod.rs", true),
+                ("service/admin/user_service.rs", true),
+            ]
+        );
+        assert_eq!(plan.modules[0].module, "crate::controller::admin::user");
+
+        plan.apply().unwrap();
+        assert_eq!(fs::read_to_string(root.path().join("controller/mod.rs")).unwrap(), "pub mod health;\npub mod admin;\n");
+        assert_eq!(fs::read_to_string(root.path().join("service/admin/mod.rs")).unwrap(), "pub mod user_service;\n");
+        assert!(fs::read_to_string(root.path().join("service/admin/user_service.rs")).unwrap().contains("pub async fn user_service("));
+    }
+
+    #[test]
+    fn test_dtos_live_in_the_crate_of_the_service_layer() {
+        let root = tempfile::tempdir().unwrap();
+        let request = GenerationRequest::new().layer("admin", "user", "create_user").layer("admin", "user_service", "");
+
+        // The default service layer is imported as `service_admin_chinese_hsk`
+        let plan = Generator::new(root.path()).unwrap().dto_dir("dto").plan(&request).unwrap();
+        let content = |path: &str| plan.changes.iter().find(|change| change.path.ends_with(path)).unwrap().content.clone();
+        assert_eq!(content("service/mod.rs"), "pub mod dto;\npub mod admin;\n");
+        assert!(content("service/dto/admin/user.rs").contains("pub struct UserRequest"));
+        assert!(content("controller/admin/user.rs").contains("use service_admin_chinese_hsk::dto::admin::user::"));
+        assert!(content("service/admin/user_service.rs").contains("use crate::dto::admin::user::"));
+
+        let error = Generator::new(root.path()).unwrap().dto_dir("").plan(&request).unwrap_err();
+        assert!(error.to_string().contains("dto_dir must not be empty"), "{}", error);
+    }
+
+    #[test]
+    fn test_reexporting_layer_is_called_through_parent_module() {
+        let root = tempfile::tempdir().unwrap();
+        let config = "[[layers]]\nname = \"controller\"\ndir = \"controller\"\n\n[[layers]]\nname = \"service\"\ndir = \"service\"\nmod_style = \"function\"\n";
+        fs::write(root.path().join("laygen.toml"), config).unwrap();
+
+        let plan = Generator::new(root.path())
+            .unwrap()
+            .plan(&GenerationRequest::new().layer("admin", "user", "create_user").layer("admin", "user_service", "create"))
+            .unwrap();
+        let content = |path: &str| plan.changes.iter().find(|change| change.path.ends_with(path)).unwrap().content.clone();
+        assert_eq!(content("service/admin/mod.rs"), "mod user_service;\npub use user_service::create;\n");
+        assert_eq!(content("service/mod.rs"), "pub mod admin;\n");
+        let controller = content("controller/admin/user.rs");
+        assert!(controller.contains("use crate::service::admin;"));
+        assert!(controller.contains("admin::create("));
+    }
+
+    #[test]
+    fn test_auto_format_records_rejected_files() {
+        if !crate::format::rustfmt_available() {
+            return;
+        }
+        let root = tempfile::tempdir().unwrap();
+        fs::write(root.path().join("broken.rs.tpl"), "pub fn (\n").unwrap();
+        let config = "[[layers]]\nname = \"controller\"\ndir = \"controller\"\n\n[[layers]]\nname = \"service\"\ndir = \"service\"\ntemplate = \"broken.rs.tpl\"\n";
+        fs::write(root.path().join("laygen.toml"), config).unwrap();
+
+        let plan = Generator::new(root.path())
+            .unwrap()
+            .plan(&GenerationRequest::new().layer("admin", "user", "create_user").layer("admin", "user_service", ""))
+            .unwrap();
+        let service = root.path().join("service/admin/user_service.rs");
+        assert_eq!(plan.unformatted.len(), 1, "{:?}", plan.unformatted);
+        assert_eq!(plan.unformatted[0].path, service);
+        assert!(plan.changes.iter().any(|change| change.path == service && change.content == "pub fn (\n"));
+    }
+
+    #[test]
+    fn test_tests_dir_wires_integration_test() {
+        let root = tempfile::tempdir().unwrap();
+        fs::write(
+            root.path().join("Cargo.toml"),
+            "[package]\nname = \"my-app\"\n\n[lib]\npath = \"lib.rs\"\n\n[dependencies]\ntokio = \"1\"\n",
+        )
+        .unwrap();
+
+        let plan = Generator::new(root.path())
+            .unwrap()
+            .tests_dir("tests/api")
+            .plan(
+                &GenerationRequest::new()
+                    .layer("admin", "user", "create_user")
+                    .layer("admin", "user_service", "")
+                    .route(Route { method: "put".to_string(), path: "/admin/users/{id}".to_string(), tag: None }),
+            )
+            .unwrap();
+        let content = |path: &str| plan.changes.iter().find(|change| change.path.ends_with(path)).unwrap().content.clone();
+        assert_eq!(content("tests/api/main.rs"), "pub mod support;\npub mod admin;\n");
+        assert_eq!(content("tests/api/admin/mod.rs"), "pub mod user;\n");
+        assert!(content("tests/api/support.rs").contains("pub async fn state() -> Option<AppState>"));
+        let test = content("tests/api/admin/user.rs");
+        assert!(test.contains("\"/admin/users/{id}\""), "{}", test);
+        assert!(test.contains("put(my_app::controller::admin::user::create_user)"), "{}", test);
+        assert!(test.contains("use my_app::controller::admin::user::UserRequest;"));
+        assert!(test.contains("async fn create_user_returns_api_response()"));
+        assert!(test.contains(".method(\"PUT\")") && test.contains(".uri(\"/admin/users/1\")"));
+
+        let dependencies = &plan.dependencies[0];
+        let dev: Vec<&str> = dependencies.dev_dependencies.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(dev, vec!["tower", "http-body-util"]);
+
+        // Refused when the test could not reach the handler: no crate, no library or a pub(crate) layer
+        let error = |manifest: Option<&str>, config: &str| {
+            let root = tempfile::tempdir().unwrap();
+            if let Some(manifest) = manifest {
+                fs::write(root.path().join("Cargo.toml"), manifest).unwrap();
+            }
+            fs::write(root.path().join("laygen.toml"), config).unwrap();
+            Generator::new(root.path())
+                .unwrap()
+                .tests_dir("tests/api")
+                .plan(&GenerationRequest::new().layer("admin", "user", "create_user").layer("admin", "user_service", ""))
+                .unwrap_err()
+                .to_string()
+        };
+        let bin = "[package]\nname = \"my-app\"\n";
+        assert!(error(None, "").contains("Cargo.toml not found"));
+        assert!(error(Some(bin), "").contains("no [lib] or src/lib.rs"));
+        let pub_crate = "[[layers]]\nname = \"controller\"\ndir = \"controller\"\nmod_style = \"pub-crate\"\n\n[[layers]]\nname = \"service\"\ndir = \"service\"\n";
+        assert!(error(Some(&format!("{}\n[lib]\npath = \"lib.rs\"\n", bin)), pub_crate).contains("pub(crate)"));
+    }
+}
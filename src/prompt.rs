@@ -0,0 +1,178 @@
+use anyhow::{bail, Result};
+use dialoguer::{Confirm, FuzzySelect, Input, Select};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::io::{self, IsTerminal};
+
+/// Validation applied to text input; the error is shown to the user.
+pub type Validator<'a> = &'a dyn Fn(&str) -> Result<(), String>;
+
+/// Asks the user questions. Interactive flows take a `&dyn Prompter` so they
+/// can run against the terminal or against a script in tests.
+pub trait Prompter {
+    /// Index of the chosen item.
+    fn select(&self, prompt: &str, items: &[String], default: usize) -> Result<usize>;
+
+    /// Index of the chosen item after type-to-filter, or `None` when cancelled.
+    fn fuzzy_select(&self, prompt: &str, items: &[String]) -> Result<Option<usize>>;
+
+    /// Text typed by the user, trimmed. Empty input is only accepted with `allow_empty`.
+    fn input(&self, prompt: &str, allow_empty: bool, validate: Option<Validator>) -> Result<String>;
+
+    fn confirm(&self, prompt: &str, default: bool) -> Result<bool>;
+
+    /// Whether there is someone to answer, as opposed to a CI job or a pipe.
+    fn is_interactive(&self) -> bool {
+        true
+    }
+}
+
+/// Prompts on the terminal with dialoguer.
+pub struct DialoguerPrompter;
+
+impl Prompter for DialoguerPrompter {
+    fn select(&self, prompt: &str, items: &[String], default: usize) -> Result<usize> {
+        Ok(Select::new()
+            .with_prompt(prompt)
+            .items(items)
+            .default(default)
+            .interact()?)
+    }
+
+    fn fuzzy_select(&self, prompt: &str, items: &[String]) -> Result<Option<usize>> {
+        Ok(FuzzySelect::new()
+            .with_prompt(prompt)
+            .items(items)
+            .default(0)
+            .interact_opt()?)
+    }
+
+    fn input(&self, prompt: &str, allow_empty: bool, validate: Option<Validator>) -> Result<String> {
+        let mut input = Input::<String>::new().with_prompt(prompt).allow_empty(allow_empty);
+        if let Some(validate) = validate {
+            input = input.validate_with(move |text: &String| validate(text.trim()));
+        }
+        Ok(input.interact_text()?.trim().to_string())
+    }
+
+    fn confirm(&self, prompt: &str, default: bool) -> Result<bool> {
+        Ok(Confirm::new().with_prompt(prompt).default(default).interact()?)
+    }
+
+    fn is_interactive(&self) -> bool {
+        io::stdin().is_terminal()
+    }
+}
+
+/// One scripted answer, consumed in order by `ScriptedPrompter`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Answer {
+    /// Pick the first item whose label contains this text
+    Choose(String),
+    /// Type this text
+    Text(String),
+    /// Accept the default item, an empty input or the default confirmation
+    Default,
+    Yes,
+    No,
+    /// Leave a fuzzy search without choosing
+    Cancel,
+}
+
+impl Answer {
+    pub fn choose(label: &str) -> Self {
+        Answer::Choose(label.to_string())
+    }
+
+    pub fn text(text: &str) -> Self {
+        Answer::Text(text.to_string())
+    }
+}
+
+/// Answers prompts from a fixed script, failing on a mismatch or when the script runs out.
+pub struct ScriptedPrompter {
+    answers: RefCell<VecDeque<Answer>>,
+}
+
+impl ScriptedPrompter {
+    pub fn new(answers: impl IntoIterator<Item = Answer>) -> Self {
+        Self { answers: RefCell::new(answers.into_iter().collect()) }
+    }
+
+    /// Answers not consumed by the flow.
+    pub fn remaining(&self) -> Vec<Answer> {
+        self.answers.borrow().iter().cloned().collect()
+    }
+
+    fn next(&self, prompt: &str) -> Result<Answer> {
+        match self.answers.borrow_mut().pop_front() {
+            Some(answer) => Ok(answer),
+            None => bail!("no scripted answer for prompt '{}'", prompt),
+        }
+    }
+
+    fn choose(prompt: &str, items: &[String], label: &str) -> Result<usize> {
+        match items.iter().position(|item| item.contains(label)) {
+            Some(index) => Ok(index),
+            None => bail!("prompt '{}' has no item matching '{}' in {:?}", prompt, label, items),
+        }
+    }
+}
+
+impl Prompter for ScriptedPrompter {
+    fn select(&self, prompt: &str, items: &[String], default: usize) -> Result<usize> {
+        match self.next(prompt)? {
+            Answer::Choose(label) => Self::choose(prompt, items, &label),
+            Answer::Default => Ok(default),
+            other => bail!("scripted answer {:?} does not fit selection '{}'", other, prompt),
+        }
+    }
+
+    fn fuzzy_select(&self, prompt: &str, items: &[String]) -> Result<Option<usize>> {
+        match self.next(prompt)? {
+            Answer::Choose(label) => Self::choose(prompt, items, &label).map(Some),
+            Answer::Cancel => Ok(None),
+            other => bail!("scripted answer {:?} does not fit search '{}'", other, prompt),
+        }
+    }
+
+    fn input(&self, prompt: &str, allow_empty: bool, validate: Option<Validator>) -> Result<String> {
+        let text = match self.next(prompt)? {
+            Answer::Text(text) => text.trim().to_string(),
+            Answer::Default => String::new(),
+            other => bail!("scripted answer {:?} does not fit input '{}'", other, prompt),
+        };
+        if text.is_empty() && !allow_empty {
+            bail!("empty scripted input for '{}'", prompt);
+        }
+        if let Some(Err(message)) = validate.map(|validate| validate(&text)) {
+            bail!("scripted input '{}' rejected by '{}': {}", text, prompt, message);
+        }
+        Ok(text)
+    }
+
+    fn confirm(&self, prompt: &str, default: bool) -> Result<bool> {
+        match self.next(prompt)? {
+            Answer::Yes => Ok(true),
+            Answer::No => Ok(false),
+            Answer::Default => Ok(default),
+            other => bail!("scripted answer {:?} does not fit confirmation '{}'", other, prompt),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scripted_prompter_answers_in_order() {
+        let prompter = ScriptedPrompter::new([Answer::choose("admin"), Answer::text(" billing "), Answer::Default]);
+        let items = vec!["✓ Done".to_string(), "admin".to_string()];
+
+        assert_eq!(prompter.select("dir", &items, 0).unwrap(), 1);
+        assert_eq!(prompter.input("name", false, None).unwrap(), "billing");
+        assert!(prompter.confirm("create?", true).unwrap());
+        assert!(prompter.select("dir", &items, 0).is_err());
+    }
+}
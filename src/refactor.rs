@@ -0,0 +1,413 @@
+use anyhow::{bail, Result};
+use proc_macro2::{Ident, LineColumn, Spacing, TokenStream, TokenTree};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use crate::check::{item_names, module_file};
+use crate::file_ops::is_valid_module_name;
+use crate::generator::to_pascal_case;
+use crate::layer::{Layer, LayerLocation};
+use crate::mod_file::{source_tree, undeclare, ModPolicy};
+use crate::plan::{GenerationPlan, Generator};
+use crate::remove::{call_chain, dto_file, file_module, mention_patterns, mentions, outer_file, Reference};
+
+/// A file that gets a new name or directory.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Move {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+/// Coordinated edits for renaming or moving a module in every layer.
+#[derive(Debug, Default)]
+pub struct Relocation {
+    pub plan: GenerationPlan,
+    pub moves: Vec<Move>,
+    /// Lines that still mention the old modules after rewriting
+    pub references: Vec<Reference>,
+}
+
+/// One module being renamed or moved.
+struct Target {
+    from: PathBuf,
+    to: PathBuf,
+    old_name: String,
+    new_name: String,
+    location: LayerLocation,
+    new_subdir: PathBuf,
+    /// Old and new `use` paths of the module, as seen from each layer
+    paths: Vec<(Vec<String>, Vec<String>)>,
+    policy: ModPolicy,
+}
+
+/// Rename `module` of the outermost layer, e.g. `admin/user` to `account`, together with
+/// the modules only it calls (`user_service` → `account_service`) and types named after it.
+pub fn rename(generator: &Generator, module: &str, new_name: &str) -> Result<Relocation> {
+    if !is_valid_module_name(new_name) {
+        bail!("invalid module name: {}", new_name);
+    }
+    relocate(generator, module, None, Some(new_name))
+}
+
+/// Move `module` of the outermost layer, e.g. `admin/user`, to `new_dir` (`api`) in every layer,
+/// together with the modules only it calls from the same subdirectory.
+pub fn move_module(generator: &Generator, module: &str, new_dir: &str) -> Result<Relocation> {
+    let new_dir: PathBuf = new_dir.split(['/', ':']).filter(|segment| !segment.is_empty()).collect();
+    if let Some(segment) = new_dir.iter().find(|segment| !is_valid_module_name(&segment.to_string_lossy())) {
+        bail!("invalid module name: {}", segment.to_string_lossy());
+    }
+    relocate(generator, module, Some(&new_dir), None)
+}
+
+fn relocate(generator: &Generator, module: &str, new_dir: Option<&Path>, new_name: Option<&str>) -> Result<Relocation> {
+    let locations = generator.resolve_locations()?;
+    let outer = outer_file(&locations, module)?;
+    let base = file_module(&outer);
+    let subdir = subdir_of(&outer, &locations[0]);
+
+    let mut members: Vec<(Layer, LayerLocation, ModPolicy, PathBuf)> = call_chain(generator, &locations, &outer)?
+        .into_iter()
+        .map(|(i, path)| {
+            let layer = &generator.layers[i];
+            (layer.clone(), locations[i].clone(), layer.mod_policy(generator.mod_order), path)
+        })
+        .collect();
+    // The DTO module mirrors the controller, so it follows along
+    if let Some((location, path)) = dto_file(generator, &locations, &outer)? {
+        let dto_dir = generator.dto_dir.as_deref().unwrap_or_default();
+        members.push((Layer::new("dto", dto_dir), location, generator.mod_policy(), path));
+    }
+    let views: Vec<LayerLocation> = members.iter().map(|(_, location, ..)| location.clone()).collect();
+
+    let mut targets = Vec::new();
+    for (layer, location, policy, from) in members {
+        let old_subdir = subdir_of(&from, &location);
+        let old_name = file_module(&from);
+        let new_name = match new_name {
+            Some(name) if old_name.starts_with(&base) => format!("{}{}", name, &old_name[base.len()..]),
+            _ => old_name.clone(),
+        };
+        let new_subdir = match new_dir {
+            Some(dir) if old_subdir == subdir => dir.to_path_buf(),
+            _ => old_subdir.clone(),
+        };
+        let to = location.path.join(&new_subdir).join(format!("{}.rs", new_name));
+        if to == from {
+            continue;
+        }
+        if to.exists() {
+            bail!("{} already exists", to.display());
+        }
+
+        let mut paths: Vec<(Vec<String>, Vec<String>)> = Vec::new();
+        for view in &views {
+            let old = layer.module_import_at(&location, view, &old_subdir, &old_name);
+            let new = layer.module_import_at(&location, view, &new_subdir, &new_name);
+            let pair = (segments(&old), segments(&new));
+            if old != new && !paths.contains(&pair) {
+                paths.push(pair);
+            }
+        }
+        // Match the longest paths first, e.g. `crate::service::admin::user` before `admin::user`
+        paths.sort_by_key(|(old, _)| std::cmp::Reverse(old.len()));
+        targets.push(Target { from, to, old_name, new_name, location, new_subdir, paths, policy });
+    }
+    let names = new_name.map(|name| (base.clone(), name.to_string()));
+    let types = match &names {
+        Some((old, new)) => defined_types(&targets, old, new)?,
+        None => BTreeMap::new(),
+    };
+
+    // Declare new directories before their files are planned, which would make them look existing
+    let mut plan = GenerationPlan::default();
+    let mut dirs = Vec::with_capacity(targets.len());
+    for target in &targets {
+        dirs.push(plan.ensure_module_dir(&target.location.path, &target.new_subdir, target.policy)?);
+    }
+
+    let sources: Vec<PathBuf> = source_tree(&generator.target)
+        .map(|entry| entry.into_path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "rs"))
+        .collect();
+    for path in &sources {
+        let content = plan.read(path)?.unwrap_or_default();
+        let member = targets.iter().find(|target| &target.from == path);
+        let rewritten = rewrite(&content, &targets, names.as_ref(), &types, member);
+        match member {
+            Some(target) => {
+                plan.write(&target.to, rewritten)?;
+                plan.remove(path);
+            }
+            None => plan.write(path, rewritten)?,
+        }
+    }
+
+    for (target, dir) in targets.iter().zip(&dirs) {
+        if let Some(mod_file) = target.from.parent().and_then(module_file) {
+            let content = plan.read(&mod_file)?.unwrap_or_default();
+            plan.write(&mod_file, undeclare(&content, &target.old_name))?;
+        }
+        let item = plan.read(&target.to)?.and_then(|content| first_function(&content));
+        plan.declare_module(dir, &target.new_name, item.as_deref(), target.policy)?;
+    }
+
+    // Paths a move leaves alone, such as a configured `import_path`, are not leftovers
+    let old_files: Vec<PathBuf> = targets.iter().map(|target| target.from.clone()).collect();
+    let new_files: Vec<PathBuf> = targets.iter().map(|target| target.to.clone()).collect();
+    let unchanged = mention_patterns(generator, &locations, &new_files);
+    let patterns: Vec<String> = mention_patterns(generator, &locations, &old_files)
+        .into_iter()
+        .filter(|pattern| !unchanged.contains(pattern))
+        .collect();
+    let mut references = Vec::new();
+    for path in sources.iter().chain(targets.iter().map(|target| &target.to)) {
+        let Some(content) = plan.read(path)? else {
+            continue;
+        };
+        for (number, line) in content.lines().enumerate() {
+            if patterns.iter().any(|pattern| mentions(line, pattern)) {
+                references.push(Reference { path: path.clone(), line: number + 1, text: line.trim().to_string() });
+            }
+        }
+    }
+
+    let moves = targets.into_iter().map(|target| Move { from: target.from, to: target.to }).collect();
+    Ok(Relocation { plan, moves, references })
+}
+
+fn subdir_of(path: &Path, location: &LayerLocation) -> PathBuf {
+    path.parent()
+        .and_then(|dir| dir.strip_prefix(&location.path).ok())
+        .unwrap_or(Path::new(""))
+        .to_path_buf()
+}
+
+fn segments(path: &str) -> Vec<String> {
+    path.split("::").map(str::to_string).collect()
+}
+
+/// Types the relocated files define whose names start with the old base name, e.g.
+/// `UserRequest`, each with its new name (`AccountRequest`).
+fn defined_types(targets: &[Target], old: &str, new: &str) -> Result<BTreeMap<String, String>> {
+    let (old_type, new_type) = (to_pascal_case(old), to_pascal_case(new));
+    let mut types = BTreeMap::new();
+    for target in targets {
+        let Ok(file) = syn::parse_file(&fs::read_to_string(&target.from)?) else {
+            continue;
+        };
+        let defined = file.items.iter().filter(|item| !matches!(item, syn::Item::Use(_) | syn::Item::Fn(_))).flat_map(item_names);
+        for name in defined {
+            if let Some(rest) = name.strip_prefix(old_type.as_str()) {
+                if rest.is_empty() || rest.starts_with(char::is_uppercase) {
+                    types.insert(name.clone(), format!("{}{}", new_type, rest));
+                }
+            }
+        }
+    }
+    Ok(types)
+}
+
+fn first_function(content: &str) -> Option<String> {
+    let file = syn::parse_file(content).ok()?;
+    file.items.iter().filter(|item| matches!(item, syn::Item::Fn(_))).flat_map(item_names).next()
+}
+
+/// `source` with the paths to `targets` updated, working on tokens so that comments,
+/// strings and formatting stay as they are. `member` is the target `source` belongs to.
+/// With a rename, `names` is the old and new base name, e.g. `user` and `account`, which
+/// also renames handlers (`user`) named after the module; `types` maps the types the
+/// relocated files define (`UserRequest`) to their new names.
+fn rewrite(
+    source: &str,
+    targets: &[Target],
+    names: Option<&(String, String)>,
+    types: &BTreeMap<String, String>,
+    member: Option<&Target>,
+) -> String {
+    let Ok(tokens) = source.parse::<TokenStream>() else {
+        return source.to_string();
+    };
+    let mut paths = Vec::new();
+    let mut functions = Vec::new();
+    collect(tokens, &mut paths, &mut functions);
+
+    let strings = |path: &[Ident]| path.iter().map(Ident::to_string).collect::<Vec<_>>();
+    let imported: Vec<&Target> = targets
+        .iter()
+        .filter(|target| paths.iter().any(|path| target.paths.iter().any(|(old, _)| strings(path).starts_with(old))))
+        .collect();
+    // Function names that follow the module: its own name and, with a rename, the base name
+    let function_name = |target: &Target, name: &str| {
+        if name == target.old_name {
+            Some(target.new_name.clone())
+        } else {
+            names.filter(|(old, _)| old == name).map(|(_, new)| new.clone())
+        }
+    };
+
+    let lines = line_starts(source);
+    let span = |ident: &Ident| ident.span();
+    let range = |first: &Ident, last: &Ident| offset(source, &lines, span(first).start())..offset(source, &lines, span(last).end());
+    let mut edits: Vec<(Range<usize>, String)> = Vec::new();
+    let rename = |ident: &Ident, new: &str, edits: &mut Vec<(Range<usize>, String)>| {
+        if ident != new {
+            edits.push((range(ident, ident), new.to_string()));
+        }
+    };
+
+    for path in &paths {
+        let segments = strings(path);
+        let full = targets.iter().find_map(|target| {
+            let (old, new) = target.paths.iter().find(|(old, _)| segments.starts_with(old))?;
+            Some((target, old.len(), new))
+        });
+        if let Some((target, len, new)) = full {
+            edits.push((range(&path[0], &path[len - 1]), new.join("::")));
+            if let Some(new) = segments.get(len).and_then(|name| function_name(target, name)) {
+                rename(&path[len], &new, &mut edits);
+            }
+        } else if let Some(target) = imported.iter().find(|target| path.len() >= 2 && segments[0] == target.old_name) {
+            rename(&path[0], &target.new_name, &mut edits);
+            if let Some(new) = function_name(target, &segments[1]) {
+                rename(&path[1], &new, &mut edits);
+            }
+        }
+    }
+
+    if let Some(target) = member {
+        for function in &functions {
+            if let Some(new) = function_name(target, &function.to_string()) {
+                rename(function, &new, &mut edits);
+            }
+        }
+    }
+    if member.is_some() || !imported.is_empty() {
+        for ident in paths.iter().flatten() {
+            if let Some(new) = types.get(&ident.to_string()) {
+                rename(ident, new, &mut edits);
+            }
+        }
+    }
+
+    edits.sort_by_key(|(range, _)| range.start);
+    edits.dedup_by_key(|(range, _)| range.start);
+    let mut output = source.to_string();
+    for (range, text) in edits.into_iter().rev() {
+        output.replace_range(range, &text);
+    }
+    output
+}
+
+/// Every `a::b::c` path and the name after each `fn`, including inside groups.
+fn collect(stream: TokenStream, paths: &mut Vec<Vec<Ident>>, functions: &mut Vec<Ident>) {
+    let tokens: Vec<TokenTree> = stream.into_iter().collect();
+    let mut i = 0;
+    while i < tokens.len() {
+        match &tokens[i] {
+            TokenTree::Group(group) => {
+                collect(group.stream(), paths, functions);
+                i += 1;
+            }
+            TokenTree::Ident(ident) => {
+                if ident == "fn" {
+                    if let Some(TokenTree::Ident(name)) = tokens.get(i + 1) {
+                        functions.push(name.clone());
+                    }
+                }
+                let mut path = vec![ident.clone()];
+                i += 1;
+                while let (Some(TokenTree::Punct(first)), Some(TokenTree::Punct(second)), Some(TokenTree::Ident(next))) =
+                    (tokens.get(i), tokens.get(i + 1), tokens.get(i + 2))
+                {
+                    if first.as_char() != ':' || first.spacing() != Spacing::Joint || second.as_char() != ':' {
+                        break;
+                    }
+                    path.push(next.clone());
+                    i += 3;
+                }
+                paths.push(path);
+            }
+            _ => i += 1,
+        }
+    }
+}
+
+fn line_starts(source: &str) -> Vec<usize> {
+    std::iter::once(0).chain(source.match_indices('\n').map(|(i, _)| i + 1)).collect()
+}
+
+/// Byte offset of a span position, whose column counts characters.
+fn offset(source: &str, lines: &[usize], position: LineColumn) -> usize {
+    let start = lines[position.line - 1];
+    source[start..].char_indices().nth(position.column).map_or(source.len(), |(i, _)| start + i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::write_tree;
+
+    fn project() -> tempfile::TempDir {
+        let root = tempfile::tempdir().unwrap();
+        write_tree(
+            root.path(),
+            &[
+                ("controller/mod.rs", "pub mod admin;\n"),
+                ("controller/admin/mod.rs", "pub mod user;\n"),
+                (
+                    "controller/admin/user.rs",
+                    "use crate::entity::UserRole;\nuse crate::service::admin::user_service;\n\n// UserRequest stays in comments\npub struct UserRequest {\n    pub role: UserRole,\n}\n\npub struct UserResponse;\n\npub async fn user(req: UserRequest) -> UserResponse {\n    user_service::user_service(req).await\n}\n",
+                ),
+                ("entity.rs", "pub enum UserRole {\n    Admin,\n}\n"),
+                ("service/mod.rs", "pub mod admin;\n"),
+                ("service/admin/mod.rs", "pub mod user_service;\n"),
+                ("service/admin/user_service.rs", "pub async fn user_service(req: UserRequest) -> UserResponse {\n    todo!()\n}\n"),
+                ("router.rs", "use crate::controller::admin::user;\n\nfn routes() {\n    route(\"/user\", post(user::user));\n}\n"),
+            ],
+        );
+        fs::write(
+            root.path().join("laygen.toml"),
+            "[[layers]]\nname = \"controller\"\ndir = \"controller\"\n\n[[layers]]\nname = \"service\"\ndir = \"service\"\nsuffix = \"_service\"\n",
+        )
+        .unwrap();
+        root
+    }
+
+    #[test]
+    fn test_rename_rewrites_both_layers() {
+        let root = project();
+        let relocation = rename(&Generator::new(root.path()).unwrap(), "admin/user", "account").unwrap();
+        assert_eq!(relocation.moves.len(), 2);
+        relocation.plan.apply().unwrap();
+
+        let read = |path: &str| fs::read_to_string(root.path().join(path)).unwrap();
+        assert_eq!(
+            read("controller/admin/account.rs"),
+            "use crate::entity::UserRole;\nuse crate::service::admin::account_service;\n\n// UserRequest stays in comments\npub struct AccountRequest {\n    pub role: UserRole,\n}\n\npub struct AccountResponse;\n\npub async fn account(req: AccountRequest) -> AccountResponse {\n    account_service::account_service(req).await\n}\n"
+        );
+        assert_eq!(read("entity.rs"), "pub enum UserRole {\n    Admin,\n}\n");
+        assert_eq!(read("service/admin/account_service.rs"), "pub async fn account_service(req: AccountRequest) -> AccountResponse {\n    todo!()\n}\n");
+        assert_eq!(read("controller/admin/mod.rs"), "pub mod account;\n");
+        assert_eq!(read("service/admin/mod.rs"), "pub mod account_service;\n");
+        assert_eq!(read("router.rs"), "use crate::controller::admin::account;\n\nfn routes() {\n    route(\"/user\", post(account::account));\n}\n");
+        assert!(!root.path().join("controller/admin/user.rs").exists());
+        assert!(relocation.references.is_empty());
+    }
+
+    #[test]
+    fn test_move_to_another_directory() {
+        let root = project();
+        let relocation = move_module(&Generator::new(root.path()).unwrap(), "admin::user", "api").unwrap();
+        assert!(root.path().join("controller/admin/user.rs").exists(), "nothing is written before apply");
+        relocation.plan.apply().unwrap();
+
+        let read = |path: &str| fs::read_to_string(root.path().join(path)).unwrap();
+        assert!(read("controller/api/user.rs").contains("\nuse crate::service::api::user_service;\n"));
+        assert_eq!(read("controller/mod.rs"), "pub mod admin;\npub mod api;\n");
+        assert_eq!(read("controller/admin/mod.rs"), "");
+        assert_eq!(read("service/api/mod.rs"), "pub mod user_service;\n");
+        assert!(read("router.rs").starts_with("use crate::controller::api::user;\n"));
+    }
+}
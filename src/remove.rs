@@ -0,0 +1,262 @@
+use anyhow::{bail, Result};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::check::{item_names, layer_modules, module_file, references, source_files};
+use crate::layer::{dto_location, LayerLocation};
+use crate::mod_file::{source_tree, undeclare};
+use crate::plan::{GenerationPlan, Generator};
+
+/// A line outside the removed files that still mentions one of them.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Reference {
+    pub path: PathBuf,
+    pub line: usize,
+    pub text: String,
+}
+
+/// Files to delete and edits to make, plus what is left for a person to clean up.
+#[derive(Debug, Default)]
+pub struct Removal {
+    pub plan: GenerationPlan,
+    pub references: Vec<Reference>,
+}
+
+/// Remove `module` of the outermost layer, e.g. `admin/user` or `admin::user`, together with
+/// the modules of inner layers that only it calls, their `mod` declarations and routes.
+pub fn remove(generator: &Generator, module: &str) -> Result<Removal> {
+    let locations = generator.resolve_locations()?;
+    let outer = outer_file(&locations, module)?;
+    let mut files: Vec<PathBuf> = call_chain(generator, &locations, &outer)?.into_iter().map(|(_, path)| path).collect();
+    files.extend(dto_file(generator, &locations, &outer)?.map(|(_, path)| path));
+
+    let mut plan = GenerationPlan::default();
+    for path in &files {
+        plan.remove(path);
+        let module = file_module(path);
+        if let Some(mod_file) = path.parent().and_then(module_file) {
+            let content = plan.read(&mod_file)?.unwrap_or_default();
+            plan.write(&mod_file, undeclare(&content, &module))?;
+        }
+    }
+
+    let handlers: Vec<String> = match syn::parse_file(&fs::read_to_string(&outer)?) {
+        Ok(file) => file.items.iter().filter(|item| matches!(item, syn::Item::Fn(_))).flat_map(item_names).collect(),
+        Err(_) => Vec::new(),
+    };
+    let routes: Vec<String> = handlers.iter().map(|handler| format!("{}::{}", file_module(&outer), handler)).collect();
+    let patterns = mention_patterns(generator, &locations, &files);
+
+    let mut references = Vec::new();
+    let sources: Vec<PathBuf> = source_tree(&generator.target)
+        .map(|entry| entry.into_path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "rs") && !files.contains(path))
+        .collect();
+    for path in sources {
+        let Some(content) = plan.read(&path)? else {
+            continue;
+        };
+        let lines: Vec<&str> = content.lines().collect();
+        let mut kept = Vec::new();
+        let mut i = 0;
+        while i < lines.len() {
+            // A `.route(...)` call, which may span several lines
+            let end = route_end(&lines, i).unwrap_or(i);
+            let block = &lines[i..=end];
+            let is_removed_route = |line: &&str| routes.iter().any(|route| mentions(line, route));
+            if (end > i || is_route(lines[i])) && block.iter().any(is_removed_route) {
+                i = end + 1;
+                continue;
+            }
+            for (offset, line) in block.iter().enumerate() {
+                if patterns.iter().any(|pattern| mentions(line, pattern)) || is_removed_route(line) {
+                    references.push(Reference { path: path.clone(), line: i + offset + 1, text: line.trim().to_string() });
+                }
+                kept.push(*line);
+            }
+            i = end + 1;
+        }
+        if kept.len() != lines.len() {
+            plan.write(&path, kept.join("\n") + "\n")?;
+        }
+    }
+    Ok(Removal { plan, references })
+}
+
+/// File of `module` in the outermost layer, e.g. `admin/user` or `admin::user`.
+pub(crate) fn outer_file(locations: &[LayerLocation], module: &str) -> Result<PathBuf> {
+    let relative: PathBuf = module.split(['/', ':']).filter(|segment| !segment.is_empty()).collect();
+    let outer = locations[0].path.join(&relative).with_extension("rs");
+    if !outer.is_file() {
+        bail!("module not found: {}", outer.display());
+    }
+    Ok(outer)
+}
+
+/// The DTO module mirroring `outer` when the project has a `dto_dir`, with its location.
+pub(crate) fn dto_file(generator: &Generator, locations: &[LayerLocation], outer: &Path) -> Result<Option<(LayerLocation, PathBuf)>> {
+    let Some(dto_dir) = generator.dto_dir.as_deref() else {
+        return Ok(None);
+    };
+    let location = dto_location(dto_dir, &generator.target, &generator.layers, locations)?;
+    let Some(subdir) = outer.parent().and_then(|dir| dir.strip_prefix(&locations[0].path).ok()) else {
+        return Ok(None);
+    };
+    let path = location.path.join(subdir).join(format!("{}.rs", file_module(outer)));
+    Ok(path.is_file().then_some((location, path)))
+}
+
+/// `outer` and, layer by layer, the modules called only from the previous ones,
+/// each with the index of its layer.
+pub(crate) fn call_chain(generator: &Generator, locations: &[LayerLocation], outer: &Path) -> Result<Vec<(usize, PathBuf)>> {
+    let mut chain = vec![(0, outer.to_path_buf())];
+    let mut callers = vec![outer.to_path_buf()];
+    for (i, layer) in generator.layers.iter().enumerate().skip(1) {
+        let callee = layer_modules(layer, &locations[i], &locations[i - 1])?;
+        let mut from_callers = BTreeSet::new();
+        let mut from_others = BTreeSet::new();
+        for path in source_files(&locations[i - 1].path) {
+            let found = references(&path, &callee)?;
+            if callers.contains(&path) {
+                from_callers.extend(found);
+            } else {
+                from_others.extend(found);
+            }
+        }
+        // Modules other callers still use stay where they are
+        callers = callee
+            .files
+            .iter()
+            .map(|(path, ..)| path.clone())
+            .filter(|path| callee.is_referenced(path, &from_callers) && !callee.is_referenced(path, &from_others))
+            .collect();
+        chain.extend(callers.iter().map(|path| (i, path.clone())));
+    }
+    Ok(chain)
+}
+
+pub(crate) fn file_module(path: &Path) -> String {
+    path.file_stem().unwrap_or_default().to_string_lossy().into_owned()
+}
+
+/// `parent::module` forms that refer to the removed files, as written in a path or `use` item.
+pub(crate) fn mention_patterns(generator: &Generator, locations: &[LayerLocation], files: &[PathBuf]) -> BTreeSet<String> {
+    let mut patterns = BTreeSet::new();
+    for path in files {
+        let module = file_module(path);
+        if let Some(parent) = path.parent().and_then(|dir| dir.file_name()) {
+            patterns.insert(format!("{}::{}", parent.to_string_lossy(), module));
+        }
+        let owner = generator.layers.iter().zip(locations).find(|(_, location)| path.starts_with(&location.path));
+        if let Some((layer, location)) = owner {
+            let subdir = path.parent().and_then(|dir| dir.strip_prefix(&location.path).ok()).unwrap_or(Path::new(""));
+            let import = layer.module_import_at(location, location, subdir, &module);
+            let segments: Vec<&str> = import.rsplit("::").take(2).collect();
+            if segments.len() == 2 {
+                patterns.insert(format!("{}::{}", segments[1], segments[0]));
+            }
+        }
+    }
+    patterns
+}
+
+/// A single-line `.route("/path", post(handler))` call in a router chain.
+fn is_route(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.starts_with(".route(") && trimmed.ends_with(')')
+}
+
+/// Last line of a `.route(` call starting at `lines[start]` that spans several lines,
+/// when it ends the call with nothing after it.
+fn route_end(lines: &[&str], start: usize) -> Option<usize> {
+    let trimmed = lines[start].trim();
+    if !trimmed.starts_with(".route(") || is_route(trimmed) {
+        return None;
+    }
+    let mut depth = 0i32;
+    for (i, line) in lines.iter().enumerate().skip(start) {
+        depth += line.matches('(').count() as i32 - line.matches(')').count() as i32;
+        if depth <= 0 {
+            return line.trim().ends_with(')').then_some(i);
+        }
+    }
+    None
+}
+
+/// Whether `line` contains `pattern` as whole path segments.
+pub(crate) fn mentions(line: &str, pattern: &str) -> bool {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    line.match_indices(pattern).any(|(start, _)| {
+        let before = line[..start].chars().next_back();
+        let after = line[start + pattern.len()..].chars().next();
+        !before.is_some_and(is_ident) && !after.is_some_and(is_ident)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::write_tree;
+
+    #[test]
+    fn test_remove_controller_and_its_service() {
+        let root = tempfile::tempdir().unwrap();
+        write_tree(
+            root.path(),
+            &[
+                ("controller/mod.rs", "pub mod admin;\n"),
+                ("controller/admin/mod.rs", "pub mod user;\npub mod report;\n"),
+                ("controller/admin/user.rs", "use service_admin_chinese_hsk::user_service;\n\npub async fn create_user() {\n    user_service::create().await;\n}\n"),
+                ("controller/admin/report.rs", "use service_admin_chinese_hsk::shared_service;\n\npub async fn report() {\n    shared_service::run().await;\n}\n"),
+                ("service/mod.rs", "pub mod admin;\n"),
+                ("service/admin/mod.rs", "pub mod user_service;\npub mod shared_service;\n"),
+                ("service/admin/user_service.rs", "use super::shared_service;\n\npub async fn create() {\n    shared_service::run().await;\n}\n"),
+                ("service/admin/shared_service.rs", "pub async fn run() {}\n"),
+                (
+                    "router.rs",
+                    "pub fn router() -> Router {\n    Router::new()\n        .route(\"/users\", post(user::create_user))\n        .route(\n            \"/users/{id}\",\n            put(user::create_user),\n        )\n        .route(\"/report\", get(report::report))\n}\n// see admin::user\n",
+                ),
+            ],
+        );
+
+        let removal = remove(&Generator::new(root.path()).unwrap(), "admin/user").unwrap();
+        assert_eq!(
+            removal.plan.removed,
+            vec![root.path().join("controller/admin/user.rs"), root.path().join("service/admin/user_service.rs")]
+        );
+        removal.plan.apply().unwrap();
+        let read = |path: &str| fs::read_to_string(root.path().join(path)).unwrap();
+        assert_eq!(read("controller/admin/mod.rs"), "pub mod report;\n");
+        assert_eq!(read("service/admin/mod.rs"), "pub mod shared_service;\n");
+        assert_eq!(
+            read("router.rs"),
+            "pub fn router() -> Router {\n    Router::new()\n        .route(\"/report\", get(report::report))\n}\n// see admin::user\n"
+        );
+        assert_eq!(
+            removal.references,
+            vec![Reference { path: root.path().join("router.rs"), line: 10, text: "// see admin::user".to_string() }]
+        );
+    }
+
+    #[test]
+    fn test_remove_follows_dto_dir() {
+        // The default service layer is the `service_admin_chinese_hsk` crate, so its DTOs live in service/
+        let root = tempfile::tempdir().unwrap();
+        fs::write(root.path().join("laygen.toml"), "dto_dir = \"dto\"\n").unwrap();
+        let generator = Generator::new(root.path()).unwrap();
+        generator
+            .plan(&crate::plan::GenerationRequest::new().layer("admin", "create_user", "").layer("admin", "create_user_service", ""))
+            .unwrap()
+            .apply()
+            .unwrap();
+        assert!(root.path().join("service/dto/admin/create_user.rs").is_file());
+
+        let removal = remove(&generator, "admin/create_user").unwrap();
+        assert!(removal.plan.removed.contains(&root.path().join("service/dto/admin/create_user.rs")));
+        removal.plan.apply().unwrap();
+        assert_eq!(fs::read_to_string(root.path().join("service/dto/admin/mod.rs")).unwrap(), "");
+        let issues = crate::check::check(&generator).unwrap();
+        assert!(issues.is_empty(), "{:?}", issues);
+    }
+}
@@ -0,0 +1,113 @@
+use serde::Serialize;
+use std::path::PathBuf;
+use crate::check::Issue;
+use crate::generator::Route;
+use crate::list::Endpoint;
+use crate::plan::{DependencyChange, GeneratedModule, GenerationPlan, Unformatted};
+use crate::remove::Reference;
+
+/// Machine-readable summary of a generation, printed with `--output json`.
+#[derive(Debug, Default, Serialize)]
+pub struct Report {
+    pub created: Vec<PathBuf>,
+    /// Existing files that were rewritten, mostly mod.rs/lib.rs declarations
+    pub modified: Vec<PathBuf>,
+    pub removed: Vec<PathBuf>,
+    pub modules: Vec<GeneratedModule>,
+    pub routes: Vec<Route>,
+    /// Dependencies added to each Cargo.toml
+    pub dependencies: Vec<DependencyReport>,
+    pub warnings: Vec<Warning>,
+}
+
+/// Result of `laygen check`, printed with `--output json`.
+#[derive(Debug, Default, Serialize)]
+pub struct CheckReport {
+    /// Problems left after any `--fix`
+    pub issues: Vec<Issue>,
+    /// Module files rewritten by `--fix`
+    pub modified: Vec<PathBuf>,
+}
+
+/// Result of `laygen list`, printed with `--output json`.
+#[derive(Debug, Default, Serialize)]
+pub struct ListReport {
+    pub endpoints: Vec<Endpoint>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DependencyReport {
+    pub manifest: PathBuf,
+    pub names: Vec<String>,
+}
+
+impl From<&DependencyChange> for DependencyReport {
+    fn from(change: &DependencyChange) -> Self {
+        Self {
+            manifest: change.manifest.clone(),
+            names: change.names().into_iter().map(str::to_string).collect(),
+        }
+    }
+}
+
+/// Something the user may need to act on.
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Warning {
+    /// The file was left as it is
+    FileExists { path: PathBuf },
+    /// Dependencies the generated code needs but were not added
+    MissingDependencies(DependencyReport),
+    /// A line still mentioning a removed module
+    RemainingReference(Reference),
+    /// A generated file rustfmt rejected, written unformatted
+    Unformatted(Unformatted),
+}
+
+impl Report {
+    pub fn new(plan: &GenerationPlan) -> Self {
+        let (created, modified) = plan.changes.iter().partition::<Vec<_>, _>(|change| change.is_new());
+        Self {
+            created: created.into_iter().map(|change| change.path.clone()).collect(),
+            modified: modified.into_iter().map(|change| change.path.clone()).collect(),
+            removed: plan.removed.clone(),
+            modules: plan.modules.clone(),
+            routes: plan.routes.clone(),
+            dependencies: Vec::new(),
+            warnings: plan
+                .skipped
+                .iter()
+                .map(|path| Warning::FileExists { path: path.clone() })
+                .chain(plan.unformatted.iter().cloned().map(Warning::Unformatted))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plan::FileChange;
+
+    #[test]
+    fn test_report_json_shape() {
+        let plan = GenerationPlan {
+            changes: vec![
+                FileChange { path: "controller/user.rs".into(), before: None, content: String::new() },
+                FileChange { path: "controller/mod.rs".into(), before: Some(String::new()), content: String::new() },
+            ],
+            routes: vec![Route { method: "post".to_string(), path: "/user".to_string(), tag: None }],
+            skipped: vec!["service/user_service.rs".into()],
+            ..GenerationPlan::default()
+        };
+
+        let json = serde_json::to_value(Report::new(&plan)).unwrap();
+        assert_eq!(json["created"], serde_json::json!(["controller/user.rs"]));
+        assert_eq!(json["modified"], serde_json::json!(["controller/mod.rs"]));
+        assert_eq!(json["routes"][0]["path"], "/user");
+        assert_eq!(
+            json["warnings"],
+            serde_json::json!([{ "kind": "file_exists", "path": "service/user_service.rs" }])
+        );
+    }
+}
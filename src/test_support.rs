@@ -0,0 +1,11 @@
+use std::fs;
+use std::path::Path;
+
+/// Write each `(path, content)` below `root`, creating the directories on the way.
+pub(crate) fn write_tree(root: &Path, files: &[(&str, &str)]) {
+    for (path, content) in files {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+}
@@ -0,0 +1,479 @@
+use anyhow::Result;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Block, List, ListState, Paragraph, Tabs};
+use ratatui::{DefaultTerminal, Frame};
+use std::path::{Path, PathBuf};
+use crate::file_ops::{get_nested_directories, is_valid_module_name};
+use crate::generator::Route;
+use crate::i18n::I18n;
+use crate::layer::LayerSelection;
+use crate::plan::{FileChange, GenerationRequest, Generator};
+
+/// HTTP methods offered for the outermost layer's route; `None` renders no route.
+const HTTP_METHODS: [Option<&str>; 6] = [None, Some("get"), Some("post"), Some("put"), Some("patch"), Some("delete")];
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "const", "crate", "else", "enum", "fn", "for", "if", "impl", "in", "let",
+    "match", "mod", "mut", "pub", "ref", "return", "self", "Self", "struct", "super", "trait", "use",
+    "where", "while",
+];
+
+/// Choices submitted from the TUI, ordered like the layers.
+pub struct TuiSelection {
+    pub selections: Vec<LayerSelection>,
+    pub route: Option<Route>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Focus {
+    Tree,
+    Form,
+    Preview,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Field {
+    FileName,
+    MethodName,
+    Suffix,
+    HttpMethod,
+}
+
+/// Form state of one layer.
+struct LayerForm {
+    /// The layer root (empty path) followed by every nested directory
+    dirs: Vec<PathBuf>,
+    tree: ListState,
+    file_name: String,
+    method_name: String,
+    suffix: bool,
+}
+
+struct App<'a> {
+    generator: &'a Generator,
+    i18n: &'a I18n,
+    forms: Vec<LayerForm>,
+    layer: usize,
+    focus: Focus,
+    field: Field,
+    http_method: usize,
+    scroll: u16,
+    /// Preview of the current form, planned again after each edit
+    preview: Option<Text<'static>>,
+    error: Option<String>,
+}
+
+/// Run the full-screen form, previewing what `generator` would write.
+/// Returns `None` when the user quits without generating.
+pub fn run(generator: &Generator, i18n: &I18n) -> Result<Option<TuiSelection>> {
+    let mut app = App::new(generator, i18n)?;
+    let mut terminal = ratatui::init();
+    let result = app.event_loop(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+impl<'a> App<'a> {
+    fn new(generator: &'a Generator, i18n: &'a I18n) -> Result<Self> {
+        let mut forms = Vec::with_capacity(generator.layers.len());
+        for (layer, location) in generator.layers.iter().zip(generator.resolve_locations()?) {
+            let mut dirs = vec![PathBuf::new()];
+            dirs.extend(get_nested_directories(&location.path)?);
+            forms.push(LayerForm {
+                dirs,
+                tree: ListState::default().with_selected(Some(0)),
+                file_name: String::new(),
+                method_name: String::new(),
+                suffix: !layer.suffix.is_empty(),
+            });
+        }
+
+        Ok(Self {
+            generator,
+            i18n,
+            forms,
+            layer: 0,
+            focus: Focus::Tree,
+            field: Field::FileName,
+            http_method: 0,
+            scroll: 0,
+            preview: None,
+            error: None,
+        })
+    }
+
+    fn event_loop(&mut self, terminal: &mut DefaultTerminal) -> Result<Option<TuiSelection>> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+            match key.code {
+                KeyCode::Esc => return Ok(None),
+                KeyCode::Char('c') if ctrl => return Ok(None),
+                KeyCode::Char('s') if ctrl => match self.submit() {
+                    Ok(selection) => return Ok(Some(selection)),
+                    Err(message) => self.error = Some(message),
+                },
+                KeyCode::Tab => self.focus = self.next_focus(),
+                KeyCode::Left => self.switch_layer(self.layer + self.forms.len() - 1),
+                KeyCode::Right => self.switch_layer(self.layer + 1),
+                _ => match self.focus {
+                    Focus::Tree => self.tree_key(key),
+                    Focus::Form => self.form_key(key),
+                    Focus::Preview => self.preview_key(key),
+                },
+            }
+            if self.focus != Focus::Preview {
+                self.preview = None;
+            }
+        }
+    }
+
+    fn next_focus(&self) -> Focus {
+        match self.focus {
+            Focus::Tree => Focus::Form,
+            Focus::Form => Focus::Preview,
+            Focus::Preview => Focus::Tree,
+        }
+    }
+
+    fn switch_layer(&mut self, layer: usize) {
+        self.layer = layer % self.forms.len();
+        self.field = Field::FileName;
+    }
+
+    fn has_suffix(&self) -> bool {
+        !self.generator.layers[self.layer].suffix.is_empty()
+    }
+
+    fn tree_key(&mut self, key: KeyEvent) {
+        let form = &mut self.forms[self.layer];
+        match key.code {
+            KeyCode::Up => form.tree.select_previous(),
+            KeyCode::Down => form.tree.select_next(),
+            KeyCode::Home => form.tree.select_first(),
+            KeyCode::End => form.tree.select_last(),
+            KeyCode::Enter => self.focus = Focus::Form,
+            _ => {}
+        }
+    }
+
+    fn form_key(&mut self, key: KeyEvent) {
+        let fields = self.fields();
+        let index = fields.iter().position(|f| *f == self.field).unwrap_or(0);
+        match key.code {
+            KeyCode::Up => self.field = fields[index.saturating_sub(1)],
+            KeyCode::Down | KeyCode::Enter => self.field = fields[(index + 1).min(fields.len() - 1)],
+            KeyCode::Char(' ') => match self.field {
+                Field::Suffix => self.forms[self.layer].suffix ^= true,
+                Field::HttpMethod => self.http_method = (self.http_method + 1) % HTTP_METHODS.len(),
+                _ => {}
+            },
+            KeyCode::Char(c) => {
+                if let Some(text) = self.text_field() {
+                    text.push(c);
+                }
+            }
+            KeyCode::Backspace => {
+                if let Some(text) = self.text_field() {
+                    text.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn preview_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Up => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::Down => self.scroll = self.scroll.saturating_add(1),
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(10),
+            KeyCode::PageDown => self.scroll = self.scroll.saturating_add(10),
+            KeyCode::Home => self.scroll = 0,
+            _ => {}
+        }
+    }
+
+    /// Fields shown for the active layer; the HTTP method applies to the outermost layer.
+    fn fields(&self) -> Vec<Field> {
+        let mut fields = vec![Field::FileName, Field::MethodName];
+        if self.has_suffix() {
+            fields.push(Field::Suffix);
+        }
+        fields.push(Field::HttpMethod);
+        fields
+    }
+
+    fn text_field(&mut self) -> Option<&mut String> {
+        let form = &mut self.forms[self.layer];
+        match self.field {
+            Field::FileName => Some(&mut form.file_name),
+            Field::MethodName => Some(&mut form.method_name),
+            _ => None,
+        }
+    }
+
+    /// Selections as `generate` would collect them from the prompts.
+    fn selections(&self) -> Vec<LayerSelection> {
+        self.generator
+            .layers
+            .iter()
+            .zip(&self.forms)
+            .map(|(layer, form)| {
+                let name = form.file_name.trim();
+                let method = form.method_name.trim();
+                let file_name = if form.suffix && !name.is_empty() && !name.ends_with(&layer.suffix) {
+                    format!("{}{}", name, layer.suffix)
+                } else {
+                    name.to_string()
+                };
+                LayerSelection {
+                    subdir: form.dirs[form.tree.selected().unwrap_or(0)].clone(),
+                    method_name: if method.is_empty() { name.to_string() } else { method.to_string() },
+                    file_name,
+                }
+            })
+            .collect()
+    }
+
+    fn route(&self, selections: &[LayerSelection]) -> Option<Route> {
+        let method = HTTP_METHODS[self.http_method]?;
+        let outer = &selections[0];
+        let mut segments: Vec<String> = outer.subdir.iter().map(|s| s.to_string_lossy().into_owned()).collect();
+        segments.push(outer.file_name.clone());
+        Some(Route {
+            method: method.to_string(),
+            path: format!("/{}", segments.join("/")),
+            tag: outer.subdir.iter().next().map(|s| s.to_string_lossy().into_owned()),
+        })
+    }
+
+    fn submit(&mut self) -> Result<TuiSelection, String> {
+        for (index, form) in self.forms.iter().enumerate() {
+            if !is_valid_module_name(form.file_name.trim()) {
+                let message = self.i18n.missing_file_name(&self.generator.layers[index].name);
+                self.layer = index;
+                self.focus = Focus::Form;
+                self.field = Field::FileName;
+                return Err(message);
+            }
+        }
+
+        let selections = self.selections();
+        let route = self.route(&selections);
+        Ok(TuiSelection { selections, route })
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [tabs_area, main_area, help_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [tree_area, form_area, preview_area] = Layout::horizontal([
+            Constraint::Percentage(25),
+            Constraint::Percentage(25),
+            Constraint::Percentage(50),
+        ])
+        .areas(main_area);
+
+        let titles = self.generator.layers.iter().map(|layer| self.i18n.layer_name(&layer.name));
+        frame.render_widget(
+            Tabs::new(titles).select(self.layer).highlight_style(Style::new().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+            tabs_area,
+        );
+
+        self.draw_tree(frame, tree_area);
+        self.draw_form(frame, form_area);
+
+        if self.preview.is_none() {
+            self.preview = Some(self.plan_preview());
+        }
+        let preview = Paragraph::new(self.preview.clone().unwrap_or_default()).scroll((self.scroll, 0)).block(self.block(
+            self.i18n.tui_preview_title(),
+            Focus::Preview,
+        ));
+        frame.render_widget(preview, preview_area);
+
+        let help = match &self.error {
+            Some(error) => Line::styled(error.clone(), Style::new().fg(Color::Red)),
+            None => Line::styled(self.i18n.tui_help(), Style::new().fg(Color::DarkGray)),
+        };
+        frame.render_widget(help, help_area);
+    }
+
+    fn block(&self, title: String, focus: Focus) -> Block<'static> {
+        let style = if self.focus == focus {
+            Style::new().fg(Color::Cyan)
+        } else {
+            Style::new()
+        };
+        Block::bordered().title(format!(" {} ", title)).border_style(style)
+    }
+
+    fn draw_tree(&mut self, frame: &mut Frame, area: ratatui::layout::Rect) {
+        let layer = &self.generator.layers[self.layer];
+        let items: Vec<String> = self.forms[self.layer]
+            .dirs
+            .iter()
+            .map(|dir| match dir.file_name() {
+                Some(name) => format!("{}{}/", "  ".repeat(dir.iter().count()), name.to_string_lossy()),
+                None => format!("{}/", layer.dir),
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(self.block(self.i18n.tui_directories_title(&layer.name), Focus::Tree))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, area, &mut self.forms[self.layer].tree);
+    }
+
+    fn draw_form(&self, frame: &mut Frame, area: ratatui::layout::Rect) {
+        let layer = &self.generator.layers[self.layer];
+        let form = &self.forms[self.layer];
+        let check = |on: bool| if on { "[x]" } else { "[ ]" };
+        let method = HTTP_METHODS[self.http_method]
+            .map(str::to_uppercase)
+            .unwrap_or_else(|| self.i18n.none_option());
+
+        let mut lines = Vec::new();
+        for field in self.fields() {
+            let (label, value) = match field {
+                Field::FileName => (self.i18n.file_name_label(), form.file_name.clone()),
+                Field::MethodName => (self.i18n.method_name_label(), form.method_name.clone()),
+                Field::Suffix => (self.i18n.suffix_label(&layer.suffix), check(form.suffix).to_string()),
+                Field::HttpMethod => (self.i18n.http_method_label(), method.clone()),
+            };
+            let active = self.focus == Focus::Form && self.field == field;
+            let cursor = if active && matches!(field, Field::FileName | Field::MethodName) { "▏" } else { "" };
+            let style = if active { Style::new().fg(Color::Yellow) } else { Style::new() };
+            lines.push(Line::styled(label, style.add_modifier(Modifier::BOLD)));
+            lines.push(Line::styled(format!("  {}{}", value, cursor), style));
+            lines.push(Line::default());
+        }
+
+        frame.render_widget(Paragraph::new(lines).block(self.block(self.i18n.tui_form_title(), Focus::Form)), area);
+    }
+
+    /// Every file the generation would write, planned without touching the disk:
+    /// new files in full, changed files (such as mod.rs) as the diff `--dry-run` prints.
+    /// A successful plan clears the error left by a failed one.
+    fn plan_preview(&mut self) -> Text<'static> {
+        let selections = self.selections();
+        if let Some((layer, _)) = self.generator.layers.iter().zip(&selections).find(|(_, selection)| selection.file_name.is_empty()) {
+            return Text::styled(self.i18n.missing_file_name(&layer.name), Style::new().fg(Color::DarkGray));
+        }
+        let route = self.route(&selections);
+        let plan = match self.generator.plan(&GenerationRequest { selections, route }) {
+            Ok(plan) => plan,
+            Err(err) => {
+                self.error = Some(err.to_string());
+                return Text::default();
+            }
+        };
+        self.error = None;
+
+        let mut lines = Vec::new();
+        for change in &plan.changes {
+            lines.push(header(&self.display_path(&change.path)));
+            if change.is_new() {
+                lines.extend(change.content.lines().map(highlight_line));
+            } else {
+                lines.extend(diff_lines(change));
+            }
+            lines.push(Line::default());
+        }
+        Text::from(lines)
+    }
+
+    fn display_path(&self, path: &Path) -> String {
+        path.strip_prefix(&self.generator.target).unwrap_or(path).display().to_string()
+    }
+}
+
+fn header(path: &str) -> Line<'static> {
+    Line::styled(format!("── {} ──", path), Style::new().fg(Color::Cyan).add_modifier(Modifier::BOLD))
+}
+
+/// Hunks of the unified diff of `change`, without the file header the preview already shows.
+fn diff_lines(change: &FileChange) -> Vec<Line<'static>> {
+    change
+        .diff()
+        .lines()
+        .skip(2)
+        .map(|line| {
+            let color = match line.chars().next() {
+                Some('+') => Color::Green,
+                Some('-') => Color::Red,
+                Some('@') => Color::Cyan,
+                _ => Color::DarkGray,
+            };
+            Line::styled(line.to_string(), Style::new().fg(color))
+        })
+        .collect()
+}
+
+/// Minimal Rust highlighting: comments, attributes, strings, keywords and types.
+fn highlight_line(line: &str) -> Line<'static> {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with("//") {
+        return Line::styled(line.to_string(), Style::new().fg(Color::DarkGray));
+    }
+    if trimmed.starts_with("#[") {
+        return Line::styled(line.to_string(), Style::new().fg(Color::Yellow));
+    }
+
+    let mut spans = Vec::new();
+    let mut rest = line;
+    while !rest.is_empty() {
+        let first = rest.chars().next().unwrap_or_default();
+        let len = if first == '"' {
+            rest[1..].find('"').map(|end| end + 2).unwrap_or(rest.len())
+        } else if first.is_alphanumeric() || first == '_' {
+            rest.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(rest.len())
+        } else {
+            first.len_utf8()
+        };
+        let (token, tail) = rest.split_at(len);
+
+        let style = if first == '"' {
+            Style::new().fg(Color::Green)
+        } else if KEYWORDS.contains(&token) {
+            Style::new().fg(Color::Magenta)
+        } else if first.is_uppercase() {
+            Style::new().fg(Color::Cyan)
+        } else {
+            Style::new()
+        };
+        spans.push(Span::styled(token.to_string(), style));
+        rest = tail;
+    }
+    Line::from(spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_lines_follow_the_unified_diff() {
+        let change = FileChange {
+            path: PathBuf::from("controller/mod.rs"),
+            before: Some("pub mod user;\n".to_string()),
+            content: "pub mod user;\npub mod profile;\n".to_string(),
+        };
+        let lines: Vec<String> = diff_lines(&change).iter().map(ToString::to_string).collect();
+        assert_eq!(lines, vec!["@@ -1 +1,2 @@", " pub mod user;", "+pub mod profile;"]);
+        assert_eq!(diff_lines(&change)[2].style.fg, Some(Color::Green));
+    }
+}
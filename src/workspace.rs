@@ -0,0 +1,241 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+
+/// A cargo workspace with several member crates.
+#[derive(Debug, Clone)]
+pub struct Workspace {
+    pub root: PathBuf,
+    pub members: Vec<Member>,
+}
+
+/// One member crate of a workspace.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Member {
+    /// Package name from Cargo.toml
+    pub name: String,
+    pub manifest_path: PathBuf,
+    /// Crate name as written in `use` paths
+    pub lib_name: String,
+    /// Directory of the crate root module, usually `src/`
+    pub src_dir: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct Metadata {
+    packages: Vec<Package>,
+    workspace_members: Vec<String>,
+    workspace_root: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    id: String,
+    name: String,
+    manifest_path: PathBuf,
+    #[serde(default)]
+    targets: Vec<Target>,
+}
+
+#[derive(Deserialize)]
+struct Target {
+    name: String,
+    kind: Vec<String>,
+    src_path: PathBuf,
+}
+
+impl Member {
+    pub fn dir(&self) -> &Path {
+        self.manifest_path.parent().unwrap_or(Path::new("."))
+    }
+
+    /// `use` path of module `module` in directory `dir` of this crate, as seen from `from`.
+    pub fn module_path(&self, dir: &Path, module: &str, from: &Member) -> String {
+        let prefix = if self == from { "crate" } else { self.lib_name.as_str() };
+        let mut segments = vec![prefix.to_string()];
+        if let Ok(relative) = dir.strip_prefix(&self.src_dir) {
+            segments.extend(relative.iter().map(|s| s.to_string_lossy().into_owned()));
+        }
+        segments.push(module.to_string());
+        segments.join("::")
+    }
+
+    /// Path of this crate relative to `from`, as written in a path dependency.
+    pub fn dependency_path(&self, from: &Member) -> PathBuf {
+        relative_path(from.dir(), self.dir())
+    }
+}
+
+impl Workspace {
+    /// Load the workspace whose root manifest is in `target`. Returns `None` when
+    /// `target` is not a workspace with more than one member.
+    pub fn load(target: &Path) -> Result<Option<Self>> {
+        let manifest = target.join("Cargo.toml");
+        if !manifest.exists() {
+            return Ok(None);
+        }
+
+        let workspace = match Self::from_cargo_metadata(&manifest) {
+            Ok(workspace) => workspace,
+            // Fall back to reading the manifests when cargo is unavailable or fails
+            Err(_) => Self::from_manifests(&manifest)?,
+        };
+
+        Ok((workspace.members.len() > 1).then_some(workspace))
+    }
+
+    fn from_cargo_metadata(manifest: &Path) -> Result<Self> {
+        let output = Command::new("cargo")
+            .args(["metadata", "--format-version", "1", "--no-deps", "--offline", "--manifest-path"])
+            .arg(manifest)
+            .output()
+            .context("failed to run cargo metadata")?;
+        if !output.status.success() {
+            anyhow::bail!("cargo metadata failed: {}", String::from_utf8_lossy(&output.stderr));
+        }
+
+        let metadata: Metadata = serde_json::from_slice(&output.stdout)?;
+        let members = metadata
+            .packages
+            .into_iter()
+            .filter(|package| metadata.workspace_members.contains(&package.id))
+            .map(|package| {
+                let lib = package.targets.iter().find(|t| t.kind.iter().any(|k| k.contains("lib")));
+                let (lib_name, src_dir) = match lib {
+                    Some(lib) => (
+                        lib.name.clone(),
+                        lib.src_path.parent().map(Path::to_path_buf).unwrap_or_default(),
+                    ),
+                    None => (
+                        package.name.replace('-', "_"),
+                        package.manifest_path.with_file_name("src"),
+                    ),
+                };
+                Member {
+                    lib_name,
+                    src_dir,
+                    name: package.name,
+                    manifest_path: package.manifest_path,
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            root: metadata.workspace_root,
+            members,
+        })
+    }
+
+    /// Read `[workspace] members` directly; supports literal paths and trailing `/*` globs.
+    fn from_manifests(manifest: &Path) -> Result<Self> {
+        let root = manifest.parent().unwrap_or(Path::new(".")).to_path_buf();
+        let value = read_manifest(manifest)?;
+        let patterns: Vec<String> = value
+            .get("workspace")
+            .and_then(|w| w.get("members"))
+            .and_then(|m| m.as_array())
+            .map(|m| m.iter().filter_map(|v| v.as_str().map(str::to_string)).collect())
+            .unwrap_or_default();
+
+        let mut dirs = Vec::new();
+        for pattern in patterns {
+            match pattern.strip_suffix("/*") {
+                Some(parent) => {
+                    let mut children: Vec<PathBuf> = fs::read_dir(root.join(parent))?
+                        .filter_map(|entry| entry.ok().map(|e| e.path()))
+                        .filter(|path| path.join("Cargo.toml").exists())
+                        .collect();
+                    children.sort();
+                    dirs.extend(children);
+                }
+                None => dirs.push(root.join(pattern)),
+            }
+        }
+
+        let mut members = Vec::new();
+        for dir in dirs {
+            let manifest_path = dir.join("Cargo.toml");
+            let value = read_manifest(&manifest_path)?;
+            let Some(name) = value
+                .get("package")
+                .and_then(|p| p.get("name"))
+                .and_then(|n| n.as_str())
+            else {
+                continue;
+            };
+            let lib = value.get("lib");
+            let lib_name = lib
+                .and_then(|l| l.get("name"))
+                .and_then(|n| n.as_str())
+                .map(str::to_string)
+                .unwrap_or_else(|| name.replace('-', "_"));
+            let src_dir = lib
+                .and_then(|l| l.get("path"))
+                .and_then(|p| p.as_str())
+                .and_then(|p| dir.join(p).parent().map(Path::to_path_buf))
+                .unwrap_or_else(|| dir.join("src"));
+
+            members.push(Member {
+                name: name.to_string(),
+                manifest_path,
+                lib_name,
+                src_dir,
+            });
+        }
+
+        Ok(Self { root, members })
+    }
+
+    pub fn member(&self, name: &str) -> Option<&Member> {
+        self.members.iter().find(|m| m.name == name || m.lib_name == name)
+    }
+}
+
+fn read_manifest(path: &Path) -> Result<toml::Value> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    toml::from_str(&content).with_context(|| format!("invalid manifest {}", path.display()))
+}
+
+/// Path of `to` relative to `from`; both are expected to be absolute or share a root.
+fn relative_path(from: &Path, to: &Path) -> PathBuf {
+    let from: Vec<Component> = from.components().collect();
+    let to: Vec<Component> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+
+    let mut path = PathBuf::new();
+    for _ in common..from.len() {
+        path.push("..");
+    }
+    for component in &to[common..] {
+        path.push(component);
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn member(name: &str, dir: &str) -> Member {
+        Member {
+            name: name.to_string(),
+            manifest_path: PathBuf::from(dir).join("Cargo.toml"),
+            lib_name: name.replace('-', "_"),
+            src_dir: PathBuf::from(dir).join("src"),
+        }
+    }
+
+    #[test]
+    fn test_cross_crate_paths() {
+        let api = member("api", "/ws/crates/api");
+        let service = member("service-core", "/ws/crates/service-core");
+        let dir = Path::new("/ws/crates/service-core/src/admin");
+
+        assert_eq!(service.module_path(dir, "user_service", &api), "service_core::admin::user_service");
+        assert_eq!(service.module_path(dir, "user_service", &service), "crate::admin::user_service");
+        assert_eq!(service.dependency_path(&api), PathBuf::from("../service-core"));
+    }
+}
@@ -0,0 +1,106 @@
+use clap::Parser;
+use laygen::cli::Cli;
+use laygen::prompt::{Answer, ScriptedPrompter};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn run(target: &Path, answers: Vec<Answer>) {
+    let cli = Cli::parse_from(["laygen", target.to_str().unwrap(), "-l", "en"]);
+    let prompter = ScriptedPrompter::new(answers);
+    cli.run_with(&prompter).unwrap();
+    assert_eq!(prompter.remaining(), Vec::new(), "unused answers");
+}
+
+fn read(path: impl AsRef<Path>) -> String {
+    fs::read_to_string(path.as_ref()).unwrap_or_else(|_| panic!("missing {}", path.as_ref().display()))
+}
+
+fn project() -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("controller/admin")).unwrap();
+    fs::write(dir.path().join("controller/mod.rs"), "pub mod admin;\n").unwrap();
+    fs::write(dir.path().join("controller/admin/mod.rs"), "").unwrap();
+    fs::create_dir_all(dir.path().join("service")).unwrap();
+    dir
+}
+
+#[test]
+fn test_generates_controller_and_service() {
+    let dir = project();
+    run(
+        dir.path(),
+        vec![
+            // Service layer: stay at the root
+            Answer::choose("Done"),
+            Answer::text("user"),
+            Answer::Default,
+            Answer::choose("Add"),
+            // Controller layer: enter admin/
+            Answer::choose("admin"),
+            Answer::choose("Done"),
+            Answer::text("user"),
+            Answer::text("create_user"),
+        ],
+    );
+
+    let controller = read(dir.path().join("controller/admin/user.rs"));
+    assert!(controller.contains("create_user"));
+    assert!(controller.contains("user_service"));
+    assert!(read(dir.path().join("service/user_service.rs")).contains("pub async fn user"));
+    assert_eq!(read(dir.path().join("controller/admin/mod.rs")), "pub mod user;\n");
+    assert_eq!(read(dir.path().join("service/mod.rs")), "pub mod user_service;\n");
+}
+
+#[test]
+fn test_new_directory_is_mirrored_to_the_next_layer() {
+    let dir = project();
+    run(
+        dir.path(),
+        vec![
+            Answer::choose("New directory"),
+            Answer::text("billing"),
+            Answer::Yes,
+            Answer::choose("Done"),
+            Answer::text("invoice"),
+            Answer::Default,
+            Answer::choose("Skip"),
+            // The controller offers the service's subpath first
+            Answer::choose("Use the same subpath as service (billing/)"),
+            Answer::text("invoice"),
+            Answer::Default,
+        ],
+    );
+
+    assert_eq!(read(dir.path().join("service/mod.rs")), "pub mod billing;\n");
+    assert_eq!(read(dir.path().join("service/billing/mod.rs")), "pub mod invoice;\n");
+    assert_eq!(read(dir.path().join("controller/mod.rs")), "pub mod admin;\npub mod billing;\n");
+    assert_eq!(read(dir.path().join("controller/billing/mod.rs")), "pub mod invoice;\n");
+    assert!(read(dir.path().join("controller/billing/invoice.rs")).contains("::invoice;"));
+}
+
+#[test]
+fn test_missing_directories_can_be_created_or_declined() {
+    let dir = tempfile::tempdir().unwrap();
+    run(
+        dir.path(),
+        vec![Answer::choose("Cancel"), Answer::choose("Cancel and exit")],
+    );
+    assert!(!dir.path().join("controller").exists());
+
+    run(
+        dir.path(),
+        vec![
+            Answer::choose("Create"),
+            Answer::choose("Done"),
+            Answer::text("health"),
+            Answer::Default,
+            Answer::choose("Skip"),
+            Answer::choose("Done"),
+            Answer::text("health"),
+            Answer::Default,
+        ],
+    );
+    assert!(dir.path().join("controller/health.rs").exists());
+    assert!(dir.path().join("service/health.rs").exists());
+}
@@ -0,0 +1,146 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+const SPEC: &str = r#"
+openapi: 3.0.3
+paths:
+  /admin/users:
+    post:
+      tags: [admin]
+      operationId: createUser
+      responses:
+        '200':
+          description: ok
+"#;
+
+/// The laygen binary run in `dir`, remembering settings in a user config inside `dir`
+/// instead of the developer's own.
+fn laygen(dir: &Path) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_laygen"));
+    command.current_dir(dir).env("LAYGEN_CONFIG", dir.join("user-config.toml"));
+    command
+}
+
+#[test]
+fn test_json_report_for_openapi_import() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("spec.yaml"), SPEC).unwrap();
+
+    let output = laygen(dir.path())
+        .args(["import-openapi", "spec.yaml", ".", "-l", "en", "--output", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    // Progress messages go to stderr, so stdout is exactly the report
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(report["created"]
+        .as_array()
+        .unwrap()
+        .contains(&serde_json::json!("./controller/admin/create_user.rs")));
+    assert_eq!(report["modified"], serde_json::json!([]));
+    assert_eq!(report["modules"][1]["module"], "service_admin_chinese_hsk::create_user_service");
+    assert_eq!(report["routes"][0]["path"], "/admin/users");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Imported 1 operations"));
+
+    let output = laygen(dir.path())
+        .args(["import-openapi", "spec.yaml", ".", "-l", "en", "--output", "json"])
+        .output()
+        .unwrap();
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["created"], serde_json::json!([]));
+    assert_eq!(report["warnings"][0]["kind"], "file_exists");
+}
+
+#[test]
+fn test_check_fails_on_undeclared_module() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("controller")).unwrap();
+    fs::write(dir.path().join("controller/mod.rs"), "").unwrap();
+
+    let check = || {
+        laygen(dir.path())
+            .args(["check", ".", "-l", "en", "--output", "json"])
+            .output()
+            .unwrap()
+    };
+    assert!(check().status.success());
+
+    fs::write(dir.path().join("controller/user.rs"), "").unwrap();
+    let output = check();
+    assert!(!output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["issues"][0]["kind"], "undeclared_module");
+    assert_eq!(report["issues"][0]["module"], "user");
+}
+
+#[test]
+fn test_missing_dependencies_without_a_terminal() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("spec.yaml"), SPEC).unwrap();
+    fs::write(dir.path().join("Cargo.toml"), "[package]\nname = \"app\"\n\n[dependencies]\n").unwrap();
+
+    // Reported rather than asked about, so the JSON report still comes out
+    let output = laygen(dir.path())
+        .args(["import-openapi", "spec.yaml", ".", "-l", "en", "--output", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["warnings"][0]["kind"], "missing_dependencies");
+    assert_eq!(report["warnings"][0]["manifest"], "./Cargo.toml");
+    assert!(!fs::read_to_string(dir.path().join("Cargo.toml")).unwrap().contains("axum"));
+
+    fs::remove_dir_all(dir.path().join("controller")).unwrap();
+    let output = laygen(dir.path())
+        .args(["import-openapi", "spec.yaml", ".", "-l", "en", "--yes"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(fs::read_to_string(dir.path().join("Cargo.toml")).unwrap().contains("axum"));
+}
+
+#[test]
+fn test_relative_target_inside_the_crate() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("Cargo.toml"), "[package]\nname = \"app\"\n\n[dependencies]\n").unwrap();
+    fs::write(dir.path().join("rustfmt.toml"), "hard_tabs = true\n").unwrap();
+    fs::create_dir_all(dir.path().join("src")).unwrap();
+    fs::write(dir.path().join("src/spec.yaml"), SPEC).unwrap();
+
+    // `.` is src/, so the manifest and rustfmt.toml are found above the working directory
+    let output = laygen(&dir.path().join("src"))
+        .args(["import-openapi", "spec.yaml", ".", "-l", "en", "--output", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["warnings"][0]["kind"], "missing_dependencies");
+    let manifest = report["warnings"][0]["manifest"].as_str().unwrap();
+    assert_eq!(fs::canonicalize(manifest).unwrap(), fs::canonicalize(dir.path().join("Cargo.toml")).unwrap());
+
+    let rustfmt = Command::new("rustfmt").arg("--version").output().is_ok_and(|output| output.status.success());
+    if rustfmt {
+        let controller = fs::read_to_string(dir.path().join("src/controller/admin/create_user.rs")).unwrap();
+        assert!(controller.contains("\n\tExtension(domain)"), "{}", controller);
+    }
+}
+
+#[test]
+fn test_remove_without_a_terminal_needs_yes() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("spec.yaml"), SPEC).unwrap();
+    let run = |args: &[&str]| laygen(dir.path()).args(args).output().unwrap();
+    assert!(run(&["import-openapi", "spec.yaml", ".", "-l", "en", "--no-deps"]).status.success());
+
+    // Nobody can confirm, so nothing is deleted without --yes
+    let output = run(&["remove", "admin/create_user", ".", "-l", "en"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--yes"));
+    assert!(dir.path().join("controller/admin/create_user.rs").exists());
+
+    let output = run(&["remove", "admin/create_user", ".", "-l", "en", "--yes"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(!dir.path().join("controller/admin/create_user.rs").exists());
+}